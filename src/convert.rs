@@ -0,0 +1,423 @@
+use super::{LanaErr, LanaExpr, LanaNumber, NativeFn};
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::Hash;
+
+/// Converts a Rust value into a Lana value.
+pub trait IntoLana {
+    fn into_lana(self) -> LanaExpr;
+}
+
+/// Converts a Lana value into a Rust value, failing with a type error like
+/// "Expected a number, got string "two"" when it has the wrong type.
+pub trait FromLana: Sized {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr>;
+}
+
+fn expected<T>(what: &str, expr: &LanaExpr) -> Result<T, LanaErr> {
+    Err(LanaErr::TypeError(format!(
+        "Expected {}, got {:?}",
+        what, expr
+    )))
+}
+
+impl IntoLana for LanaExpr {
+    fn into_lana(self) -> LanaExpr {
+        self
+    }
+}
+
+impl FromLana for LanaExpr {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        Ok(expr.clone())
+    }
+}
+
+impl IntoLana for () {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::Nil
+    }
+}
+
+impl IntoLana for LanaNumber {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::Number(self)
+    }
+}
+
+impl FromLana for LanaNumber {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::Number(n) => Ok(n.clone()),
+            other => expected("a number", other),
+        }
+    }
+}
+
+impl IntoLana for f64 {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::float(self)
+    }
+}
+
+/// Any number can be read as a float, losing precision if needed.
+impl FromLana for f64 {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::Number(n) => Ok(n.to_f64()),
+            other => expected("a number", other),
+        }
+    }
+}
+
+impl IntoLana for f32 {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::float(self.into())
+    }
+}
+
+impl FromLana for f32 {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        f64::from_lana(expr).map(|n| n as f32)
+    }
+}
+
+/// Integers are read from exact Lana integers that fit the integer type.
+macro_rules! integer_conversions {
+    ($($int:ty),*) => {
+        $(
+            impl IntoLana for $int {
+                fn into_lana(self) -> LanaExpr {
+                    LanaExpr::Number(BigInt::from(self).into())
+                }
+            }
+
+            impl FromLana for $int {
+                fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+                    let n = match expr {
+                        LanaExpr::Number(LanaNumber::Int(n)) => <$int>::try_from(*n).ok(),
+                        LanaExpr::Number(LanaNumber::BigInt(n)) => <$int>::try_from(n.as_ref()).ok(),
+                        _ => None,
+                    };
+
+                    n.map_or_else(
+                        || expected(concat!("an integer (", stringify!($int), ")"), expr),
+                        Ok,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl IntoLana for bool {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::Bool(self)
+    }
+}
+
+impl FromLana for bool {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::Bool(b) => Ok(*b),
+            other => expected("a boolean", other),
+        }
+    }
+}
+
+impl IntoLana for String {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::String(self)
+    }
+}
+
+impl IntoLana for &str {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::String(self.into())
+    }
+}
+
+impl FromLana for String {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::String(s) => Ok(s.clone()),
+            other => expected("a string", other),
+        }
+    }
+}
+
+/// `None` is `nil`.
+impl<T: IntoLana> IntoLana for Option<T> {
+    fn into_lana(self) -> LanaExpr {
+        match self {
+            Some(value) => value.into_lana(),
+            None => LanaExpr::Nil,
+        }
+    }
+}
+
+impl<T: FromLana> FromLana for Option<T> {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::Nil => Ok(None),
+            other => T::from_lana(other).map(Some),
+        }
+    }
+}
+
+/// Vectors become Lana vectors, and are read from both lists and vectors.
+impl<T: IntoLana> IntoLana for Vec<T> {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::Vector(self.into_iter().map(IntoLana::into_lana).collect())
+    }
+}
+
+impl<T: FromLana> FromLana for Vec<T> {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::List(items, _) | LanaExpr::Vector(items) => {
+                items.iter().map(T::from_lana).collect()
+            }
+            other => expected("a list or a vector", other),
+        }
+    }
+}
+
+impl<K: IntoLana, V: IntoLana> IntoLana for HashMap<K, V> {
+    fn into_lana(self) -> LanaExpr {
+        LanaExpr::Map(
+            self.into_iter()
+                .map(|(key, value)| (key.into_lana(), value.into_lana()))
+                .collect::<IndexMap<_, _>>(),
+        )
+    }
+}
+
+impl<K: FromLana + Eq + Hash, V: FromLana> FromLana for HashMap<K, V> {
+    fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+        match expr {
+            LanaExpr::Map(map) => map
+                .iter()
+                .map(|(key, value)| Ok((K::from_lana(key)?, V::from_lana(value)?)))
+                .collect(),
+            other => expected("a map", other),
+        }
+    }
+}
+
+/// Tuples are vectors of a fixed size.
+macro_rules! tuple_conversions {
+    ($($len:literal => ($($name:ident),+)),*) => {
+        $(
+            impl<$($name: IntoLana),+> IntoLana for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn into_lana(self) -> LanaExpr {
+                    let ($($name,)+) = self;
+
+                    LanaExpr::Vector(vec![$($name.into_lana()),+])
+                }
+            }
+
+            impl<$($name: FromLana),+> FromLana for ($($name,)+) {
+                fn from_lana(expr: &LanaExpr) -> Result<Self, LanaErr> {
+                    match expr {
+                        LanaExpr::List(items, _) | LanaExpr::Vector(items) if items.len() == $len => {
+                            let mut items = items.iter();
+
+                            Ok(($($name::from_lana(items.next().expect("length was checked"))?,)+))
+                        }
+                        other => expected(concat!("a sequence of ", $len, " elements"), other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+tuple_conversions!(
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D)
+);
+
+/// What a typed native function may return: a value, or a `Result` for functions that can fail.
+pub trait IntoLanaResult {
+    fn into_lana_result(self) -> Result<LanaExpr, LanaErr>;
+}
+
+impl<T: IntoLana> IntoLanaResult for T {
+    fn into_lana_result(self) -> Result<LanaExpr, LanaErr> {
+        Ok(self.into_lana())
+    }
+}
+
+impl<T: IntoLana> IntoLanaResult for Result<T, LanaErr> {
+    fn into_lana_result(self) -> Result<LanaExpr, LanaErr> {
+        self.map(IntoLana::into_lana)
+    }
+}
+
+/// Rust functions whose arguments can be read from Lana values, and whose result can be turned
+/// into one. See `NativeFn::from_fn`.
+pub trait IntoNativeFn<Args> {
+    fn into_native_fn(self, name: &str) -> NativeFn;
+}
+
+macro_rules! native_fn_conversions {
+    ($($arity:literal => ($($arg:ident),*)),*) => {
+        $(
+            impl<F, R, $($arg),*> IntoNativeFn<($($arg,)*)> for F
+            where
+                F: Fn($($arg),*) -> R + 'static,
+                R: IntoLanaResult,
+                $($arg: FromLana),*
+            {
+                #[allow(unused_variables, unused_mut)]
+                fn into_native_fn(self, name: &str) -> NativeFn {
+                    NativeFn::new(name, move |args| {
+                        // The arity is checked before native functions are called
+                        let mut args = args.iter();
+
+                        self($($arg::from_lana(args.next().expect("arity was checked"))?),*)
+                            .into_lana_result()
+                    })
+                    .arity($arity, Some($arity))
+                }
+            }
+        )*
+    };
+}
+
+native_fn_conversions!(
+    0 => (),
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D),
+    5 => (A, B, C, D, E)
+);
+
+impl NativeFn {
+    /// Turns a Rust function into a native Lana function, checking how many arguments it is
+    /// called with and converting them with `FromLana`:
+    ///
+    /// ```
+    /// use lana::{Interpreter, LanaExpr, NativeFn};
+    ///
+    /// let lana = Interpreter::new();
+    /// lana.register(NativeFn::from_fn("repeat", |s: String, n: usize| s.repeat(n)));
+    ///
+    /// assert_eq!(Ok(LanaExpr::String("abab".into())), lana.eval_str("(repeat \"ab\" 2)"));
+    /// ```
+    pub fn from_fn<Args>(name: &str, function: impl IntoNativeFn<Args>) -> NativeFn {
+        function.into_native_fn(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interpreter;
+
+    #[test]
+    fn it_converts_numbers() {
+        assert_eq!(Ok(1.5), f64::from_lana(&1.5.into_lana()));
+        assert_eq!(Ok(3), i32::from_lana(&3.into_lana()));
+    }
+
+    #[test]
+    fn it_errors_on_fractional_integers() {
+        let result = u8::from_lana(&LanaExpr::float(1.5));
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Expected an integer (u8), got number '1.5'".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_values_of_the_wrong_type() {
+        let result = f64::from_lana(&LanaExpr::String("two".into()));
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Expected a number, got string \"two\"".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_converts_nil_to_none() {
+        assert_eq!(Ok(None), Option::<String>::from_lana(&LanaExpr::Nil));
+        assert_eq!(LanaExpr::Nil, None::<bool>.into_lana());
+    }
+
+    #[test]
+    fn it_converts_collections() {
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), vec![(1, true)]);
+
+        let result = HashMap::<String, Vec<(i64, bool)>>::from_lana(&map.clone().into_lana());
+
+        assert_eq!(Ok(map), result);
+    }
+
+    #[test]
+    fn it_reads_vectors_from_lists() {
+        let list = LanaExpr::List(vec![LanaExpr::int(1)], None);
+
+        assert_eq!(Ok(vec![1.0]), Vec::<f64>::from_lana(&list));
+    }
+
+    #[test]
+    fn it_turns_typed_functions_into_native_functions() {
+        let lana = Interpreter::new();
+        lana.register(NativeFn::from_fn("hypot", |a: f64, b: f64| a.hypot(b)));
+
+        assert_eq!(Ok(LanaExpr::float(5.0)), lana.eval_str("(hypot 3 4)"));
+    }
+
+    #[test]
+    fn it_checks_arguments_of_typed_functions() {
+        let lana = Interpreter::new();
+        lana.register(NativeFn::from_fn("half", |n: f64| n / 2.0));
+
+        let arity_error = lana.eval_str("(half)").map_err(LanaErr::without_context);
+        let type_error = lana
+            .eval_str("(half \"two\")")
+            .map_err(LanaErr::without_context);
+
+        assert_eq!(
+            Err(LanaErr::ArityError(
+                "'half' expected 1 argument(s), got 0".into()
+            )),
+            arity_error
+        );
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Expected a number, got string \"two\"".into()
+            )),
+            type_error
+        );
+    }
+
+    #[test]
+    fn it_propagates_errors_from_typed_functions() {
+        let lana = Interpreter::new();
+        lana.register(NativeFn::from_fn("fail", || -> Result<(), LanaErr> {
+            Err(LanaErr::Reason("failed".into()))
+        }));
+
+        let result = lana.eval_str("(fail)").map_err(LanaErr::without_context);
+
+        assert_eq!(Err(LanaErr::Reason("failed".into())), result);
+    }
+}
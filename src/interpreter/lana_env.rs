@@ -1,29 +1,210 @@
+use super::modules::{Modules, Namespace, CORE_NS};
 use super::prelude;
-use super::LanaExpr;
+use super::{LanaErr, LanaExpr, NativeFn};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::rc::Rc;
 
-type EnvData = HashMap<String, LanaExpr>;
+pub type EnvData = HashMap<String, LanaExpr>;
 
-pub struct LanaEnv<'a> {
-    pub data: EnvData,
-    pub outer: Option<&'a LanaEnv<'a>>,
+/// A frame of bindings. Frames are reference counted, so cloning a `LanaEnv` is cheap and the clone
+/// shares its bindings with the original. This is what allows lambdas to capture the environment
+/// they were defined in.
+#[derive(Clone)]
+pub struct LanaEnv {
+    data: Rc<Frame>,
+    outer: Option<Rc<LanaEnv>>,
+    /// The namespace code in this frame was read in, shared by all frames extending it.
+    ns: Rc<Namespace>,
 }
 
-impl<'a> LanaEnv<'a> {
-    pub fn default() -> Self {
-        LanaEnv {
-            data: prelude::prelude(),
+/// The bindings of a frame. Lambdas capture the frame they are defined in, so a lambda stored in it,
+/// like one bound by `let` or `defn`, forms a reference cycle with it. Counting those lambdas lets
+/// the frame tell when nothing else refers to it, and break the cycles by dropping its bindings.
+struct Frame {
+    bindings: RefCell<EnvData>,
+    /// How many of the bindings are lambdas capturing this frame.
+    self_refs: Cell<usize>,
+}
+
+impl Frame {
+    fn new(data: EnvData) -> Rc<Self> {
+        Rc::new(Frame {
+            bindings: RefCell::new(data),
+            self_refs: Cell::new(0),
+        })
+    }
+
+    /// Drops the bindings, which must not be borrowed.
+    fn clear(&self) {
+        // Taken out first, as dropping the bindings may drop environments of this frame
+        let bindings = std::mem::take(&mut *self.bindings.borrow_mut());
+        self.self_refs.set(0);
+        drop(bindings);
+    }
+}
+
+/// The environment of the `user` namespace, on top of a frame with the prelude, including
+/// `core.lana`, loaded.
+impl Default for LanaEnv {
+    fn default() -> Self {
+        LanaEnv::with_modules(Modules::default())
+    }
+}
+
+impl LanaEnv {
+    /// Like `default`, but with the given modules, e.g. to look for them in another search path.
+    pub(super) fn with_modules(modules: Modules) -> Self {
+        let modules = Rc::new(modules);
+        let core = LanaEnv {
+            data: Frame::new(prelude::prelude()),
             outer: None,
+            ns: Rc::new(Namespace::new(CORE_NS, modules.clone())),
+        };
+        prelude::load_core(&core);
+
+        core.with_namespace(Namespace::new("user", modules))
+    }
+
+    pub fn extend(&self, data: EnvData) -> Self {
+        LanaEnv {
+            data: Frame::new(data),
+            outer: Some(Rc::new(self.clone())),
+            ns: self.ns.clone(),
         }
     }
 
+    /// Starts an empty frame for the definitions of `ns`.
+    pub(super) fn with_namespace(&self, ns: Namespace) -> Self {
+        LanaEnv {
+            data: Frame::new(EnvData::new()),
+            outer: Some(Rc::new(self.clone())),
+            ns: Rc::new(ns),
+        }
+    }
+
+    /// The outermost frame, which holds the prelude.
+    pub(super) fn root(&self) -> LanaEnv {
+        match &self.outer {
+            Some(outer) => outer.root(),
+            None => self.clone(),
+        }
+    }
+
+    pub(super) fn ns(&self) -> &Namespace {
+        &self.ns
+    }
+
+    /// Looks `symbol` up, first as a qualified symbol like `alias/name`, then through the frames.
     pub fn get(&self, symbol: &str) -> Option<LanaExpr> {
-        match self.data.get(symbol) {
-            Some(expr) => Some(expr.clone()),
+        self.get_qualified(symbol).or_else(|| self.lookup(symbol))
+    }
+
+    fn lookup(&self, symbol: &str) -> Option<LanaExpr> {
+        match self.get_own(symbol) {
+            Some(expr) => Some(expr),
             None => match &self.outer {
-                Some(outer_env) => outer_env.get(symbol),
+                Some(outer_env) => outer_env.lookup(symbol),
                 None => None,
             },
         }
     }
+
+    fn get_qualified(&self, symbol: &str) -> Option<LanaExpr> {
+        let (module, name) = symbol.split_once('/')?;
+
+        if module == CORE_NS {
+            return self.root().get_own(name);
+        }
+
+        self.ns.module(module)?.get_own(name)
+    }
+
+    /// Looks `symbol` up in this frame only, ignoring the frames it extends.
+    pub(super) fn get_own(&self, symbol: &str) -> Option<LanaExpr> {
+        self.data.bindings.borrow().get(symbol).cloned()
+    }
+
+    pub fn define(&self, symbol: &str, value: LanaExpr) {
+        let self_refs = &self.data.self_refs;
+        if self.is_captured_by(&value) {
+            self_refs.set(self_refs.get() + 1);
+        }
+
+        let old = self
+            .data
+            .bindings
+            .borrow_mut()
+            .insert(symbol.to_string(), value);
+        if old.as_ref().is_some_and(|old| self.is_captured_by(old)) {
+            self_refs.set(self_refs.get() - 1);
+        }
+    }
+
+    fn is_captured_by(&self, value: &LanaExpr) -> bool {
+        match value {
+            LanaExpr::Lambda(lambda) | LanaExpr::Macro(lambda) => {
+                Rc::ptr_eq(&lambda.env.data, &self.data)
+            }
+            _ => false,
+        }
+    }
+
+    /// Rebinds `symbol` in the nearest frame that defines it.
+    pub fn set(&self, symbol: &str, value: LanaExpr) -> Result<(), LanaErr> {
+        let defined = self.data.bindings.borrow().contains_key(symbol);
+        if defined {
+            self.define(symbol, value);
+
+            return Ok(());
+        }
+
+        match &self.outer {
+            Some(outer_env) => outer_env.set(symbol, value),
+            None => Err(LanaErr::UndefinedSymbol(symbol.to_string())),
+        }
+    }
+
+    /// Unbinds everything in this frame and the ones it extends, and unloads the modules of its
+    /// namespace. Frames are freed by themselves once only the lambdas stored in them refer to
+    /// them, but loaded modules refer back to their namespace, and lambdas stored elsewhere, like in
+    /// an atom, can still form cycles, which are only freed this way.
+    pub(crate) fn clear(&self) {
+        for module in self.ns.unload_modules() {
+            module.clear_frames();
+        }
+        self.clear_frames();
+    }
+
+    fn clear_frames(&self) {
+        self.data.clear();
+
+        if let Some(outer) = &self.outer {
+            outer.clear_frames();
+        }
+    }
+
+    /// Defines a function implemented in Rust under its name, so builtins can be added at runtime.
+    pub fn register(&self, function: NativeFn) {
+        let name = function.name.clone();
+
+        self.define(&name, LanaExpr::Native(function));
+    }
+}
+
+/// Once only the lambdas stored in its frame refer to it, nothing else can reach them either.
+impl Drop for LanaEnv {
+    fn drop(&mut self) {
+        let self_refs = self.data.self_refs.get();
+
+        if self_refs > 0 && Rc::strong_count(&self.data) == self_refs + 1 {
+            self.data.clear();
+        }
+    }
+}
+
+impl PartialEq for LanaEnv {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
 }
@@ -1,42 +1,150 @@
 mod lana_env;
+mod modules;
 mod prelude;
 
-use super::{LanaErr, LanaExpr, LanaLambda};
+use super::{
+    parser, FromLana, LanaArity, LanaAtom, LanaErr, LanaError, LanaExpr, LanaLambda, LanaNumber,
+    LanaRecord, LanaRecordType, LanaRegex, NativeFn, SrcSpan, StackFrame, Tokenizer,
+};
+use indexmap::{IndexMap, IndexSet};
+use lana_env::EnvData;
 pub use lana_env::LanaEnv;
+pub use modules::{eval_file, eval_source};
 pub use prelude::prelude;
-use std::collections::HashMap;
+use std::path::Path;
 use std::rc::Rc;
 
-pub fn eval(expr: &LanaExpr, env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
+/// What evaluating a form produced. Forms in tail position are handed back to `eval` instead of
+/// being evaluated recursively, so tail calls don't grow the Rust stack.
+enum Evaluated<'a> {
+    Value(LanaExpr),
+    /// A form in tail position within the one evaluated, so it is borrowed rather than copied.
+    TailCall(&'a LanaExpr, LanaEnv),
+    /// A form built while evaluating, like a macro expansion, to evaluate in its place.
+    Expanded(Rc<LanaExpr>, LanaEnv),
+    /// A tail call into the body of a Lana function, which replaces the caller's stack frame, along
+    /// with the file the function was defined in.
+    Call(Rc<LanaExpr>, LanaEnv, StackFrame, Option<Rc<Path>>),
+}
+
+/// What `eval` knows about where it is, to add to errors.
+#[derive(Default)]
+struct Context {
+    /// The innermost form read from source, to point errors from forms without a span at.
+    span: Option<SrcSpan>,
+    frame: Option<StackFrame>,
+    file: Option<Rc<Path>>,
+}
+
+impl Context {
+    fn add_to(&self, err: LanaErr) -> LanaErr {
+        let err = match &self.span {
+            Some(span) => err.located(span),
+            None => err,
+        };
+        let err = raised_in(err, self.file.as_ref());
+
+        match &self.frame {
+            Some(frame) => err.with_frame(frame.clone()),
+            None => err,
+        }
+    }
+}
+
+pub fn eval(expr: &LanaExpr, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let mut context = Context::default();
+    let mut evaluated = eval_tail_calls(expr, env.clone(), &mut context)?;
+
+    // Function bodies and expansions aren't part of `expr`, so they are kept alive here while
+    // `eval_tail_calls` borrows them.
+    loop {
+        evaluated = match evaluated {
+            Evaluated::Value(value) => return Ok(value),
+            Evaluated::TailCall(..) => unreachable!("eval_tail_calls follows tail calls"),
+            Evaluated::Expanded(expr, env) => eval_tail_calls(&expr, env, &mut context)?,
+            Evaluated::Call(body, env, frame, file) => {
+                context.frame = Some(frame);
+                context.file = file;
+
+                eval_tail_calls(&body, env, &mut context)?
+            }
+        };
+    }
+}
+
+/// Evaluates `expr`, following the tail calls within it, until it produces a value or a form that
+/// isn't part of it.
+fn eval_tail_calls(
+    expr: &LanaExpr,
+    mut env: LanaEnv,
+    context: &mut Context,
+) -> Result<Evaluated<'static>, LanaErr> {
+    let mut expr = expr;
+
+    loop {
+        if let Some(expr_span) = expr.span() {
+            context.span = Some(expr_span.clone());
+        }
+
+        match eval_step(expr, &env).map_err(|err| context.add_to(err))? {
+            Evaluated::TailCall(next_expr, next_env) => {
+                expr = next_expr;
+                env = next_env;
+            }
+            Evaluated::Value(value) => return Ok(Evaluated::Value(value)),
+            Evaluated::Expanded(expr, env) => return Ok(Evaluated::Expanded(expr, env)),
+            Evaluated::Call(body, env, frame, file) => {
+                return Ok(Evaluated::Call(body, env, frame, file))
+            }
+        }
+    }
+}
+
+fn eval_step<'a>(expr: &'a LanaExpr, env: &LanaEnv) -> Result<Evaluated<'a>, LanaErr> {
     match expr {
-        LanaExpr::Nil => Ok(LanaExpr::Nil),
-        LanaExpr::Bool(_) => Ok(expr.clone()),
-        LanaExpr::String(_) => Ok(expr.clone()),
-        LanaExpr::Keyword(_) => Ok(expr.clone()),
-        LanaExpr::Number(_) => Ok(expr.clone()),
+        LanaExpr::Nil => Ok(Evaluated::Value(LanaExpr::Nil)),
+        LanaExpr::Bool(_) => Ok(Evaluated::Value(expr.clone())),
+        LanaExpr::String(_) => Ok(Evaluated::Value(expr.clone())),
+        LanaExpr::Keyword(_) => Ok(Evaluated::Value(expr.clone())),
+        LanaExpr::Number(_) => Ok(Evaluated::Value(expr.clone())),
+        LanaExpr::Regex(_) => Ok(Evaluated::Value(expr.clone())),
         LanaExpr::Symbol(k) => env
             .get(k)
-            .ok_or_else(|| LanaErr::Reason(format!("Undefined symbol '{}'", k))),
-        LanaExpr::List(list) => {
+            .map(Evaluated::Value)
+            .ok_or_else(|| LanaErr::UndefinedSymbol(k.clone())),
+        LanaExpr::List(list, spans) => {
             let (first_form, arg_forms) = list
                 .split_first()
                 .ok_or_else(|| LanaErr::Reason("Expected a non-empty list".into()))?;
+            let spans = spans.as_ref().map(|spans| spans.items.as_slice());
 
             match eval_built_in_form(first_form, arg_forms, env) {
                 Some(result) => result,
                 None => {
-                    let first_eval = eval(first_form, env)?;
+                    let first_eval = eval_located(first_form, spans.and_then(|s| s.first()), env)?;
+                    let args = || eval_args(arg_forms, spans.map(|s| &s[1..]), env);
+
                     match first_eval {
-                        LanaExpr::Func(function) => {
-                            let args_eval: Result<Vec<LanaExpr>, LanaErr> =
-                                arg_forms.iter().map(|arg| eval(arg, env)).collect();
-                            function(&args_eval?)
+                        LanaExpr::Func(_)
+                        | LanaExpr::EnvFunc(_)
+                        | LanaExpr::Native(_)
+                        | LanaExpr::Keyword(_) => {
+                            apply(&first_eval, &args()?, env).map(Evaluated::Value)
                         }
                         LanaExpr::Lambda(lambda) => {
-                            let new_env = &mut env_for_lambda(lambda.params, arg_forms, env)?;
-                            eval(&lambda.body, new_env)
+                            let (body, new_env) = env_for_lambda(&lambda, &args()?)?;
+                            let frame = StackFrame {
+                                name: lambda.name.clone(),
+                                call_site: expr.span().cloned(),
+                            };
+
+                            Ok(Evaluated::Call(body, new_env, frame, lambda.file.clone()))
+                        }
+                        LanaExpr::Macro(lambda) => {
+                            let expansion = expand_macro(&lambda, arg_forms)?;
+                            Ok(Evaluated::Expanded(Rc::new(expansion), env.clone()))
                         }
-                        _ => Err(LanaErr::Reason(format!(
+                        _ => Err(LanaErr::TypeError(format!(
                             "First form must be a function, got {:?}",
                             first_eval
                         ))),
@@ -44,78 +152,325 @@ pub fn eval(expr: &LanaExpr, env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
                 }
             }
         }
-        LanaExpr::Func(_) => Err(LanaErr::Reason("Unexpected function".to_string())),
+        LanaExpr::Vector(vector) => {
+            Ok(Evaluated::Value(LanaExpr::Vector(eval_exprs(vector, env)?)))
+        }
+        LanaExpr::Map(map) => {
+            let mut evaled_map = IndexMap::new();
+
+            for (key, value) in map {
+                evaled_map.insert(eval(key, env)?, eval(value, env)?);
+            }
+
+            Ok(Evaluated::Value(LanaExpr::Map(evaled_map)))
+        }
+        LanaExpr::Set(set) => {
+            let evaled_set: Result<IndexSet<LanaExpr>, LanaErr> =
+                set.iter().map(|item| eval(item, env)).collect();
+
+            Ok(Evaluated::Value(LanaExpr::Set(evaled_set?)))
+        }
+        LanaExpr::Func(_) | LanaExpr::EnvFunc(_) | LanaExpr::Native(_) => {
+            Err(LanaErr::Reason("Unexpected function".to_string()))
+        }
         LanaExpr::Lambda(_) => Err(LanaErr::Reason("Unexpected lambda".to_string())),
+        LanaExpr::Macro(_) => Err(LanaErr::Reason("Unexpected macro".to_string())),
+        LanaExpr::Error(_) | LanaExpr::Atom(_) | LanaExpr::Record(_) => {
+            Ok(Evaluated::Value(expr.clone()))
+        }
+    }
+}
+
+/// Records that `err` was raised in `file`, when it is known.
+fn raised_in(err: LanaErr, file: Option<&Rc<Path>>) -> LanaErr {
+    match file {
+        Some(file) => err.in_file(file),
+        None => err,
+    }
+}
+
+/// Calls `function` with already evaluated arguments. This is how builtins call back into Lana
+/// code, e.g. the function given to `map`.
+pub fn apply(function: &LanaExpr, args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    match function {
+        LanaExpr::Func(function) => function(args),
+        LanaExpr::EnvFunc(function) => function(args, env),
+        LanaExpr::Native(function) => function.call(args, env),
+        LanaExpr::Keyword(_) => get_keyword(function, args),
+        LanaExpr::Lambda(lambda) => {
+            let (body, new_env) = env_for_lambda(lambda, args)?;
+
+            eval(&body, &new_env).map_err(|err| {
+                raised_in(err, lambda.file.as_ref()).with_frame(StackFrame {
+                    name: lambda.name.clone(),
+                    call_site: None,
+                })
+            })
+        }
+        _ => Err(LanaErr::TypeError(format!(
+            "Expected a function, got {:?}",
+            function
+        ))),
     }
 }
 
-fn eval_exprs(args: &[LanaExpr], env: &mut LanaEnv) -> Result<Vec<LanaExpr>, LanaErr> {
+/// Calling a keyword looks it up in a map or a record, like `get`.
+fn get_keyword(keyword: &LanaExpr, args: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
+    let (coll, default) = match args {
+        [coll] => (coll, LanaExpr::Nil),
+        [coll, default] => (coll, default.clone()),
+        _ => {
+            return Err(LanaErr::ArityError(format!(
+                "Expected 1-2 arguments, got {}",
+                args.len()
+            )))
+        }
+    };
+
+    let value = match coll {
+        LanaExpr::Map(map) => map.get(keyword),
+        LanaExpr::Record(record) => record.get(keyword),
+        LanaExpr::Nil => None,
+        other => {
+            return Err(LanaErr::TypeError(format!(
+                "Cannot look up {:?} in {:?}",
+                keyword, other
+            )))
+        }
+    };
+
+    Ok(value.cloned().unwrap_or(default))
+}
+
+fn eval_exprs(args: &[LanaExpr], env: &LanaEnv) -> Result<Vec<LanaExpr>, LanaErr> {
     args.iter().map(|arg| eval(arg, env)).collect()
 }
 
-fn parse_list_of_symbol_strings(expr: Rc<LanaExpr>) -> Result<Vec<String>, LanaErr> {
-    let list = match expr.as_ref() {
-        LanaExpr::List(s) => Ok(s.clone()),
-        _ => Err(LanaErr::Reason("Expected lambda args to be a list".into())),
-    }?;
+/// Evaluates the arguments of a call, pointing errors at the argument that raised them.
+fn eval_args(
+    args: &[LanaExpr],
+    spans: Option<&[SrcSpan]>,
+    env: &LanaEnv,
+) -> Result<Vec<LanaExpr>, LanaErr> {
+    args.iter()
+        .enumerate()
+        .map(|(index, arg)| eval_located(arg, spans.and_then(|s| s.get(index)), env))
+        .collect()
+}
+
+fn eval_located(
+    expr: &LanaExpr,
+    span: Option<&SrcSpan>,
+    env: &LanaEnv,
+) -> Result<LanaExpr, LanaErr> {
+    eval(expr, env).map_err(|err| match span {
+        Some(span) => err.located(span),
+        None => err,
+    })
+}
+
+/// Binds `value` to `pattern` in `env`. A pattern is either a symbol, a list (or vector) of
+/// patterns, which destructures a sequence by position, or a map of patterns to keys, which
+/// destructures a map by key. Missing elements are bound to `nil`. A sequence pattern may end with
+/// `& rest`, binding `rest` to a list of the remaining elements.
+fn bind_pattern(pattern: &LanaExpr, value: LanaExpr, env: &LanaEnv) -> Result<(), LanaErr> {
+    match pattern {
+        LanaExpr::Symbol(name) => {
+            env.define(name, value);
+
+            Ok(())
+        }
+        LanaExpr::Map(patterns) => {
+            let map = match value {
+                LanaExpr::Map(map) => map,
+                LanaExpr::Nil => IndexMap::new(),
+                other => {
+                    return Err(LanaErr::TypeError(format!(
+                        "Cannot destructure {:?} as a map",
+                        other
+                    )))
+                }
+            };
+
+            for (pattern, key) in patterns {
+                let value = map.get(key).cloned().unwrap_or(LanaExpr::Nil);
+
+                bind_pattern(pattern, value, env)?;
+            }
+
+            Ok(())
+        }
+        LanaExpr::List(patterns, _) | LanaExpr::Vector(patterns) => {
+            let mut values = match value {
+                LanaExpr::List(values, _) | LanaExpr::Vector(values) => values.into_iter(),
+                LanaExpr::Nil => vec![].into_iter(),
+                other => {
+                    return Err(LanaErr::TypeError(format!(
+                        "Cannot destructure {:?} as a sequence",
+                        other
+                    )))
+                }
+            };
+
+            let (positional, rest) = split_rest_pattern(patterns)?;
+
+            for pattern in positional {
+                bind_pattern(pattern, values.next().unwrap_or(LanaExpr::Nil), env)?;
+            }
+
+            if let Some(rest) = rest {
+                bind_pattern(rest, LanaExpr::List(values.collect(), None), env)?;
+            }
+
+            Ok(())
+        }
+        _ => Err(LanaErr::Reason(format!(
+            "Expected a symbol, a sequence or a map to bind to, got {:?}",
+            pattern
+        ))),
+    }
+}
+
+/// Splits a list of patterns into the positional ones and the one following `&`, if any.
+fn split_rest_pattern(patterns: &[LanaExpr]) -> Result<(&[LanaExpr], Option<&LanaExpr>), LanaErr> {
+    let ampersand = patterns
+        .iter()
+        .position(|pattern| *pattern == LanaExpr::Symbol("&".into()));
 
-    list.iter()
-        .map(|arg| match arg {
-            LanaExpr::Symbol(s) => Ok(s.clone()),
+    match ampersand {
+        None => Ok((patterns, None)),
+        Some(index) => match &patterns[index + 1..] {
+            [rest] => Ok((&patterns[..index], Some(rest))),
             _ => Err(LanaErr::Reason(
-                "Expected symbols in lambda argument list".into(),
+                "Expected exactly one pattern after '&'".into(),
             )),
-        })
-        .collect()
+        },
+    }
 }
 
-fn env_for_lambda<'a>(
-    params: Rc<LanaExpr>,
+/// Picks the arity of `lambda` that accepts `args` and builds the environment its body runs in.
+/// The new frame extends the environment the lambda was defined in, so free variables are
+/// resolved lexically.
+fn env_for_lambda(
+    lambda: &LanaLambda,
     args: &[LanaExpr],
-    outer_env: &'a mut LanaEnv,
-) -> Result<LanaEnv<'a>, LanaErr> {
-    let symbols = parse_list_of_symbol_strings(params)?;
+) -> Result<(Rc<LanaExpr>, LanaEnv), LanaErr> {
+    let mut fixed = None;
+    let mut variadic = None;
+
+    for arity in lambda.arities.iter() {
+        match split_rest_pattern(&arity.params)? {
+            (positional, None) if positional.len() == args.len() => fixed = fixed.or(Some(arity)),
+            (positional, Some(_)) if positional.len() <= args.len() => {
+                variadic = variadic.or(Some(arity))
+            }
+            _ => {}
+        }
+    }
 
-    if symbols.len() != args.len() {
-        return Err(LanaErr::Reason(format!(
-            "Expected {} argument(s), got {}",
-            symbols.len(),
-            args.len()
-        )));
+    // Fixed arities take precedence over variadic ones
+    let arity = fixed
+        .or(variadic)
+        .ok_or_else(|| arity_error(lambda, args.len()))?;
+    let new_env = lambda.env.extend(EnvData::new());
+
+    bind_pattern(
+        &LanaExpr::List(arity.params.to_vec(), None),
+        LanaExpr::List(args.to_vec(), None),
+        &new_env,
+    )?;
+
+    Ok((arity.body.clone(), new_env))
+}
+
+/// Lists the fixed arities, then the smallest variadic one. Fixed arities a variadic one covers are
+/// left out, so `((x) ...) ((x y) ...) ((x y & z) ...)` expects "1 or at least 2" arguments.
+fn arity_error(lambda: &LanaLambda, args_count: usize) -> LanaErr {
+    let mut fixed = vec![];
+    let mut at_least: Option<usize> = None;
+
+    for arity in lambda.arities.iter() {
+        match split_rest_pattern(&arity.params) {
+            Ok((positional, Some(_))) => {
+                at_least = Some(at_least.map_or(positional.len(), |n| n.min(positional.len())))
+            }
+            _ => fixed.push(arity.params.len()),
+        }
     }
 
-    let vs = eval_exprs(args, outer_env)?;
-    let mut data: HashMap<String, LanaExpr> = HashMap::new();
+    fixed.retain(|n| at_least.is_none_or(|min| *n < min));
+    fixed.sort_unstable();
+    fixed.dedup();
+
+    let mut expected: Vec<String> = fixed.iter().map(ToString::to_string).collect();
+    expected.extend(at_least.map(|n| format!("at least {}", n)));
+
+    let name = match &lambda.name {
+        Some(name) => format!("'{}'", name),
+        None => "Lambda".into(),
+    };
+
+    LanaErr::ArityError(format!(
+        "{} expected {} argument(s), got {}",
+        name,
+        expected.join(" or "),
+        args_count
+    ))
+}
+
+/// Runs a macro body with its parameters bound to the unevaluated argument forms. The returned
+/// form is what gets evaluated in place of the macro call.
+fn expand_macro(lambda: &LanaLambda, arg_forms: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
+    let (body, macro_env) = env_for_lambda(lambda, arg_forms)?;
+
+    eval(&body, &macro_env)
+}
 
-    for (k, v) in symbols.iter().zip(vs.iter()) {
-        data.insert(k.clone(), v.clone());
+/// Expands `expr` once if it is a call to a macro, returning `None` otherwise.
+fn macroexpand_1(expr: &LanaExpr, env: &LanaEnv) -> Result<Option<LanaExpr>, LanaErr> {
+    if let LanaExpr::List(list, _) = expr {
+        if let Some((LanaExpr::Symbol(name), arg_forms)) = list.split_first() {
+            if let Some(LanaExpr::Macro(lambda)) = env.get(name) {
+                return expand_macro(&lambda, arg_forms).map(Some);
+            }
+        }
     }
 
-    Ok(LanaEnv {
-        data,
-        outer: Some(outer_env),
-    })
+    Ok(None)
 }
 
-fn eval_built_in_form(
+fn eval_built_in_form<'a>(
     expr: &LanaExpr,
-    args: &[LanaExpr],
-    env: &mut LanaEnv,
-) -> Option<Result<LanaExpr, LanaErr>> {
-    match expr {
+    args: &'a [LanaExpr],
+    env: &LanaEnv,
+) -> Option<Result<Evaluated<'a>, LanaErr>> {
+    let result = match expr {
         LanaExpr::Symbol(s) => match s.as_str() {
-            "if" => Some(eval_if_args(args, env)),
-            "def" => Some(eval_def_args(args, env)),
-            "fn" => Some(eval_lambda_args(args)),
-            "defn" => Some(eval_defn_args(args, env)),
-            "do" => Some(eval_do_args(args, env)),
-            _ => None,
+            "if" => return Some(eval_if_args(args, env)),
+            "case" => return Some(eval_case_args(args, env)),
+            "do" => return Some(eval_do_args(args, env)),
+            "let" => return Some(eval_let_args(args, env)),
+            "def" => eval_def_args(args, env),
+            "set!" => eval_set_args(args, env),
+            "fn" => eval_lambda_args(args, env),
+            "defn" => eval_defn_args(args, env),
+            "quote" => eval_quote_args(args),
+            "quasiquote" => eval_quasiquote_args(args, env),
+            "defmacro" => eval_defmacro_args(args, env),
+            "defrecord" => eval_defrecord_args(args, env),
+            "macroexpand-1" => eval_macroexpand_args(args, env, false),
+            "macroexpand" => eval_macroexpand_args(args, env, true),
+            "try" => eval_try_args(args, env),
+            "ns" => modules::eval_ns_args(args, env),
+            _ => return None,
         },
-        _ => None,
-    }
+        _ => return None,
+    };
+
+    Some(result.map(Evaluated::Value))
 }
 
-fn eval_if_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
+fn eval_if_args<'a>(args: &'a [LanaExpr], env: &LanaEnv) -> Result<Evaluated<'a>, LanaErr> {
     let condition_expr = args
         .first()
         .ok_or_else(|| LanaErr::Reason("Expected if condition".into()))?;
@@ -127,9 +482,10 @@ fn eval_if_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaEr
         )));
     }
 
-    let branch_name = match eval(condition_expr, env)? {
-        LanaExpr::Bool(false) | LanaExpr::Nil => "else",
-        _ => "then",
+    let branch_name = if eval(condition_expr, env)?.is_truthy() {
+        "then"
+    } else {
+        "else"
     };
 
     let branch_index = if branch_name == "then" { 1 } else { 2 };
@@ -138,10 +494,66 @@ fn eval_if_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaEr
         .get(branch_index)
         .ok_or_else(|| LanaErr::Reason(format!("Expected if's {} branch", branch_name)))?;
 
-    eval(if_branch, env)
+    Ok(Evaluated::TailCall(if_branch, env.clone()))
+}
+
+/// `(case expr constant form ... default)` evaluates the form following the constant equal to the
+/// value of `expr`. A list of constants matches any of them. Without a default, a value that
+/// matches no constant is an error.
+fn eval_case_args<'a>(args: &'a [LanaExpr], env: &LanaEnv) -> Result<Evaluated<'a>, LanaErr> {
+    let (expr, clauses) = args
+        .split_first()
+        .ok_or_else(|| LanaErr::Reason("Expected a value to match".into()))?;
+    let value = eval(expr, env)?;
+
+    for clause in clauses.chunks(2) {
+        let form = match clause {
+            [constants, form] => {
+                if !case_matches(constants, &value)? {
+                    continue;
+                }
+
+                form
+            }
+            [default] => default,
+            _ => unreachable!("chunks are never empty"),
+        };
+
+        return Ok(Evaluated::TailCall(form, env.clone()));
+    }
+
+    Err(LanaErr::Reason(format!(
+        "No case clause matches {:?}",
+        value
+    )))
+}
+
+fn case_matches(constants: &LanaExpr, value: &LanaExpr) -> Result<bool, LanaErr> {
+    let constants = match constants {
+        LanaExpr::List(constants, _) => constants.as_slice(),
+        constant => std::slice::from_ref(constant),
+    };
+
+    for constant in constants {
+        match constant {
+            LanaExpr::Keyword(_) | LanaExpr::Number(_) | LanaExpr::String(_) => {
+                if constant == value {
+                    return Ok(true);
+                }
+            }
+            other => {
+                return Err(LanaErr::Reason(format!(
+                    "Expected case constants to be keywords, numbers or strings, got {:?}",
+                    other
+                )))
+            }
+        }
+    }
+
+    Ok(false)
 }
 
-fn eval_def_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
+fn eval_def_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
     let variable = args
         .first()
         .ok_or_else(|| LanaErr::Reason("Expected variable name".into()))?;
@@ -166,12 +578,78 @@ fn eval_def_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaE
         .ok_or_else(|| LanaErr::Reason("Expected assignment value".into()))?;
 
     let value = eval(value_expr, env)?;
-    env.data.insert(var_name, value.clone());
+    env.define(&var_name, value.clone());
+
+    Ok(value)
+}
+
+/// Rebinds a variable in the nearest scope that defines it, unlike `def`, which always defines it in
+/// the current one.
+fn eval_set_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let (name, value_expr) = match args {
+        [LanaExpr::Symbol(name), value_expr] => (name, value_expr),
+        [other, _] => {
+            return Err(LanaErr::Reason(format!(
+                "Expected variable name to be a symbol, got {:?}",
+                other
+            )))
+        }
+        _ => {
+            return Err(LanaErr::Reason(format!(
+                "Expected a variable name and a value, got {} arguments",
+                args.len()
+            )))
+        }
+    };
+
+    let value = eval(value_expr, env)?;
+    env.set(name, value.clone())?;
 
     Ok(value)
 }
 
-fn eval_lambda_args(args: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
+fn eval_lambda_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    make_lambda(None, args, env).map(LanaExpr::Lambda)
+}
+
+/// Builds a lambda from either a single `(params) body` pair or a sequence of `((params) body)`
+/// clauses, one per arity.
+fn make_lambda(
+    name: Option<String>,
+    args: &[LanaExpr],
+    env: &LanaEnv,
+) -> Result<LanaLambda, LanaErr> {
+    let arities = if is_multi_arity(args) {
+        args.iter()
+            .map(|clause| match clause {
+                LanaExpr::List(clause, _) => parse_arity(clause),
+                _ => unreachable!("is_multi_arity only accepts lists"),
+            })
+            .collect::<Result<Vec<LanaArity>, LanaErr>>()?
+    } else {
+        vec![parse_arity(args)?]
+    };
+
+    Ok(LanaLambda {
+        name,
+        arities: Rc::new(arities),
+        env: env.clone(),
+        file: env.ns().current_file(),
+    })
+}
+
+/// A lambda definition has multiple arities when each of its arguments is a clause starting with
+/// a parameter list. A single arity definition has exactly two arguments, so it is only mistaken
+/// for a multi-arity one when its first parameter is destructured *and* its body is a call on a
+/// list, like `(fn ((a b)) ((get-fn a) b))`.
+fn is_multi_arity(args: &[LanaExpr]) -> bool {
+    args.iter().all(|clause| match clause {
+        LanaExpr::List(clause, _) => matches!(clause.first(), Some(LanaExpr::List(_, _))),
+        _ => false,
+    })
+}
+
+fn parse_arity(args: &[LanaExpr]) -> Result<LanaArity, LanaErr> {
     let params = args
         .first()
         .ok_or_else(|| LanaErr::Reason("Expected lambda args and body".into()))?;
@@ -185,13 +663,20 @@ fn eval_lambda_args(args: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
         ));
     }
 
-    Ok(LanaExpr::Lambda(LanaLambda {
-        body: Rc::new(body.clone()),
+    let params = match params {
+        LanaExpr::List(params, _) => Ok(params),
+        _ => Err(LanaErr::Reason("Expected lambda args to be a list".into())),
+    }?;
+
+    split_rest_pattern(params)?;
+
+    Ok(LanaArity {
         params: Rc::new(params.clone()),
-    }))
+        body: Rc::new(body.clone()),
+    })
 }
 
-fn eval_defn_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
+fn eval_defn_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
     let (variable, lambda_args) = args
         .split_first()
         .ok_or_else(|| LanaErr::Reason("Expected lambda name".into()))?;
@@ -204,188 +689,2504 @@ fn eval_defn_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, Lana
         ))),
     }?;
 
-    let lambda = eval_lambda_args(lambda_args)?;
+    let lambda = LanaExpr::Lambda(make_lambda(Some(var_name.clone()), lambda_args, env)?);
 
-    env.data.insert(var_name, lambda.clone());
+    env.define(&var_name, lambda.clone());
 
     Ok(lambda)
 }
 
-fn eval_do_args(args: &[LanaExpr], env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
-    if args.is_empty() {
-        return Ok(LanaExpr::Nil);
-    }
+/// `(defrecord Name (fields))` declares a record type, and defines its constructor, `->Name`, which
+/// takes the value of each field in order, and its predicate, `name?`.
+fn eval_defrecord_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let (name, fields) = match args {
+        [LanaExpr::Symbol(name), LanaExpr::List(fields, _) | LanaExpr::Vector(fields)] => {
+            (name, fields)
+        }
+        _ => {
+            return Err(LanaErr::Reason(
+                "Expected a record name and a list of fields".into(),
+            ))
+        }
+    };
 
-    let mut result = LanaExpr::Number(0.0);
+    let fields = fields
+        .iter()
+        .map(|field| match field {
+            LanaExpr::Symbol(field) => Ok(format!(":{}", field)),
+            other => Err(LanaErr::Reason(format!(
+                "Expected field name to be a symbol, got {:?}",
+                other
+            ))),
+        })
+        .collect::<Result<Vec<String>, LanaErr>>()?;
+    let fields_count = fields.len();
+    let record_type = Rc::new(LanaRecordType {
+        name: name.clone(),
+        fields,
+    });
+
+    let constructor_type = record_type.clone();
+    let constructor = NativeFn::new(&format!("->{}", name), move |args| {
+        Ok(LanaExpr::Record(Rc::new(LanaRecord {
+            record_type: constructor_type.clone(),
+            values: args.to_vec(),
+        })))
+    })
+    .arity(fields_count, Some(fields_count))
+    .doc(&format!("Creates a {}", name));
+
+    let predicate = NativeFn::new(&format!("{}?", kebab_case(name)), move |args| {
+        Ok(LanaExpr::Bool(matches!(
+            &args[0],
+            LanaExpr::Record(record) if Rc::ptr_eq(&record.record_type, &record_type)
+        )))
+    })
+    .arity(1, Some(1))
+    .doc(&format!("Checks whether a value is a {}", name));
 
-    for expr in args {
-        result = eval(expr, env)?;
-    }
+    env.register(predicate);
+    env.register(constructor.clone());
 
-    Ok(result)
+    Ok(LanaExpr::Native(constructor))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_expects_nil_to_evaluate_to_itself() {
-        let expr = LanaExpr::Nil;
-
-        let result = eval(&expr, &mut LanaEnv::default());
+/// Converts a record name like `TodoItem` to `todo-item`.
+fn kebab_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut previous: Option<char> = None;
 
-        assert_eq!(Ok(LanaExpr::Nil), result)
+    for c in name.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            result.push('-');
+        }
+        result.extend(c.to_lowercase());
+        previous = Some(c);
     }
 
-    #[test]
-    fn it_expects_string_to_evaluate_to_itself() {
-        let expr = LanaExpr::String("hello world".into());
-
-        let result = eval(&expr, &mut LanaEnv::default());
-
-        assert_eq!(Ok(LanaExpr::String("hello world".into())), result)
-    }
+    result
+}
 
-    #[test]
-    fn it_expects_keyword_to_evaluate_to_itself() {
-        let expr = LanaExpr::Keyword(":ok".into());
+fn eval_defmacro_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let (variable, lambda_args) = args
+        .split_first()
+        .ok_or_else(|| LanaErr::Reason("Expected macro name".into()))?;
 
-        let result = eval(&expr, &mut LanaEnv::default());
+    let var_name = match variable {
+        LanaExpr::Symbol(name) => Ok(name.clone()),
+        _ => Err(LanaErr::Reason(format!(
+            "Expected macro name to be a symbol, got {:?}",
+            variable
+        ))),
+    }?;
 
-        assert_eq!(Ok(LanaExpr::Keyword(":ok".into())), result)
-    }
+    let lambda = LanaExpr::Macro(make_lambda(Some(var_name.clone()), lambda_args, env)?);
 
-    #[test]
-    fn it_expects_symbol_to_evaluate_to_its_value() {
-        let expr = LanaExpr::Symbol("my-var".into());
-        let mut env = LanaEnv::default();
-        env.data.insert("my-var".into(), LanaExpr::Nil);
+    env.define(&var_name, lambda.clone());
 
-        let result = eval(&expr, &mut env);
+    Ok(lambda)
+}
 
-        assert_eq!(Ok(LanaExpr::Nil), result)
+fn eval_macroexpand_args(
+    args: &[LanaExpr],
+    env: &LanaEnv,
+    expand_all: bool,
+) -> Result<LanaExpr, LanaErr> {
+    if args.len() != 1 {
+        return Err(LanaErr::Reason(format!(
+            "Expected 1 argument, got {}",
+            args.len()
+        )));
     }
 
-    #[test]
-    fn it_errors_on_undefined_symbols() {
-        let expr = LanaExpr::Symbol("my-var".into());
-        let mut env = LanaEnv::default();
+    let mut form = eval(&args[0], env)?;
 
-        let result = eval(&expr, &mut env);
+    while let Some(expansion) = macroexpand_1(&form, env)? {
+        form = expansion;
 
-        assert_eq!(
-            Err(LanaErr::Reason("Undefined symbol 'my-var'".to_string())),
-            result
-        )
+        if !expand_all {
+            break;
+        }
     }
 
-    #[test]
-    fn it_expects_macro_do_to_return_nil_if_no_args_are_given() {
-        let expr = LanaExpr::List(vec![LanaExpr::Symbol("do".into())]);
-        let mut env = LanaEnv::default();
-
-        let result = eval(&expr, &mut env);
+    Ok(form)
+}
 
-        assert_eq!(Ok(LanaExpr::Nil), result)
+fn eval_quote_args(args: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
+    match args {
+        [expr] => Ok(expr.clone()),
+        _ => Err(LanaErr::Reason(format!(
+            "Expected 1 argument, got {}",
+            args.len()
+        ))),
     }
+}
 
-    #[test]
-    fn it_expects_do_macro_to_eval_multiple_exprs() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("do".into()),
-            LanaExpr::List(vec![
-                LanaExpr::Symbol("def".into()),
-                LanaExpr::Symbol("var1".into()),
-                LanaExpr::Number(1.0),
-            ]),
-            LanaExpr::List(vec![
-                LanaExpr::Symbol("def".into()),
-                LanaExpr::Symbol("var2".into()),
-                LanaExpr::Number(2.0),
-            ]),
-        ]);
-        let mut env = LanaEnv::default();
-        env.data.insert("var1".into(), LanaExpr::Number(0.0));
-        env.data.insert("var2".into(), LanaExpr::Number(0.0));
-
-        eval(&expr, &mut env).ok();
-
-        assert_eq!(LanaExpr::Number(1.0), env.data.get("var1").unwrap().clone());
-        assert_eq!(LanaExpr::Number(2.0), env.data.get("var2").unwrap().clone());
+fn eval_quasiquote_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    match args {
+        [expr] => quasiquote(expr, env),
+        _ => Err(LanaErr::Reason(format!(
+            "Expected 1 argument, got {}",
+            args.len()
+        ))),
     }
+}
 
-    #[test]
-    fn it_expects_do_macro_to_return_last_eval() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("do".into()),
-            LanaExpr::Bool(true),
-            LanaExpr::Bool(false),
-        ]);
-        let mut env = LanaEnv::default();
+/// Returns `expr` unevaluated, except for the `unquote` forms in it, which are evaluated, and the
+/// `splice-unquote` forms, whose resulting list or vector is spliced into the enclosing list, vector
+/// or set. Maps are walked too, but can't be spliced into.
+fn quasiquote(expr: &LanaExpr, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    match expr {
+        LanaExpr::List(list, _) => match unquoted_form("unquote", expr) {
+            Some(unquoted) => eval(unquoted, env),
+            None => Ok(LanaExpr::List(quasiquote_items(list, env)?, None)),
+        },
+        LanaExpr::Vector(vector) => Ok(LanaExpr::Vector(quasiquote_items(vector, env)?)),
+        LanaExpr::Set(set) => {
+            let items: Vec<LanaExpr> = set.iter().cloned().collect();
 
-        let result = eval(&expr, &mut env).expect("Could not eval do macro");
+            Ok(LanaExpr::Set(
+                quasiquote_items(&items, env)?.into_iter().collect(),
+            ))
+        }
+        LanaExpr::Map(map) => {
+            let mut result = IndexMap::new();
 
-        assert_eq!(LanaExpr::Bool(false), result);
+            for (key, value) in map {
+                result.insert(quasiquote(key, env)?, quasiquote(value, env)?);
+            }
+
+            Ok(LanaExpr::Map(result))
+        }
+        _ => Ok(expr.clone()),
     }
+}
 
-    #[test]
-    fn it_expects_nil_to_be_falsey() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("if".into()),
-            LanaExpr::Nil,
-            LanaExpr::Number(1.0),
-            LanaExpr::Number(2.0),
-        ]);
-        let mut env = LanaEnv::default();
+fn quasiquote_items(items: &[LanaExpr], env: &LanaEnv) -> Result<Vec<LanaExpr>, LanaErr> {
+    let mut result = vec![];
+
+    for item in items {
+        match unquoted_form("splice-unquote", item) {
+            Some(spliced) => match eval(spliced, env)? {
+                LanaExpr::List(items, _) | LanaExpr::Vector(items) => result.extend(items),
+                LanaExpr::Nil => {}
+                other => {
+                    return Err(LanaErr::Reason(format!(
+                        "Expected splice-unquote to evaluate to a list or a vector, got {:?}",
+                        other
+                    )))
+                }
+            },
+            None => result.push(quasiquote(item, env)?),
+        }
+    }
 
-        let result = eval(&expr, &mut env).expect("Could not eval if macro");
+    Ok(result)
+}
 
-        assert_eq!(LanaExpr::Number(2.0), result);
+fn unquoted_form<'a>(form_name: &str, expr: &'a LanaExpr) -> Option<&'a LanaExpr> {
+    match expr {
+        LanaExpr::List(list, _) => match list.as_slice() {
+            [LanaExpr::Symbol(name), unquoted] if name == form_name => Some(unquoted),
+            _ => None,
+        },
+        _ => None,
     }
+}
 
-    #[test]
-    fn it_expects_false_to_be_falsey() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("if".into()),
-            LanaExpr::Bool(false),
-            LanaExpr::Number(1.0),
-            LanaExpr::Number(2.0),
-        ]);
-        let mut env = LanaEnv::default();
+fn eval_let_args<'a>(args: &'a [LanaExpr], env: &LanaEnv) -> Result<Evaluated<'a>, LanaErr> {
+    let (bindings, body) = args
+        .split_first()
+        .ok_or_else(|| LanaErr::Reason("Expected let bindings".into()))?;
+
+    let bindings = match bindings {
+        LanaExpr::List(bindings, _) | LanaExpr::Vector(bindings) => Ok(bindings),
+        _ => Err(LanaErr::Reason(format!(
+            "Expected let bindings to be a list or a vector, got {:?}",
+            bindings
+        ))),
+    }?;
+
+    if !bindings.len().is_multiple_of(2) {
+        return Err(LanaErr::Reason(
+            "Expected an even number of forms in let bindings".into(),
+        ));
+    }
+
+    let let_env = env.extend(EnvData::new());
+
+    for binding in bindings.chunks(2) {
+        let value = eval(&binding[1], &let_env)?;
+
+        bind_pattern(&binding[0], value, &let_env)?;
+    }
+
+    eval_do_args(body, &let_env)
+}
+
+struct CatchClause<'a> {
+    kind: Option<&'a str>,
+    binding: &'a str,
+    body: &'a [LanaExpr],
+}
+
+/// `(try body* (catch [kind] e handler*)* (finally cleanup*)?)`. An error raised by the body is
+/// handled by the first `catch` clause without a kind or with the error's kind, e.g.
+/// `:type-error`. `finally` runs in any case, and its result is discarded.
+fn eval_try_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let mut body = vec![];
+    let mut catch_clauses = vec![];
+    let mut finally_body = None;
+
+    for arg in args {
+        let clause = match arg {
+            LanaExpr::List(list, _) => match list.split_first() {
+                Some((LanaExpr::Symbol(name), clause_args))
+                    if name == "catch" || name == "finally" =>
+                {
+                    Some((name.as_str(), clause_args))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match clause {
+            Some(("catch", clause_args)) if finally_body.is_none() => {
+                catch_clauses.push(parse_catch_clause(clause_args)?)
+            }
+            Some(("finally", clause_args)) if finally_body.is_none() => {
+                finally_body = Some(clause_args)
+            }
+            None if catch_clauses.is_empty() && finally_body.is_none() => body.push(arg.clone()),
+            _ => {
+                return Err(LanaErr::Reason(
+                    "Expected try's body to be followed only by catch clauses and a finally clause"
+                        .into(),
+                ))
+            }
+        }
+    }
+
+    let result = eval_body(&body, env).or_else(|err| {
+        let error = err.to_error_value();
+
+        let clause = catch_clauses
+            .iter()
+            .find(|clause| clause.kind.is_none_or(|kind| kind == error.kind));
+
+        match clause {
+            Some(clause) => {
+                let catch_env = env.extend(EnvData::new());
+                catch_env.define(clause.binding, LanaExpr::Error(error));
+
+                eval_body(clause.body, &catch_env)
+            }
+            None => Err(err),
+        }
+    });
+
+    if let Some(finally_body) = finally_body {
+        eval_body(finally_body, env)?;
+    }
+
+    result
+}
+
+fn parse_catch_clause(args: &[LanaExpr]) -> Result<CatchClause<'_>, LanaErr> {
+    let (kind, rest) = match args.split_first() {
+        Some((LanaExpr::Keyword(kind), rest)) => (Some(kind.as_str()), rest),
+        _ => (None, args),
+    };
+
+    match rest.split_first() {
+        Some((LanaExpr::Symbol(binding), body)) => Ok(CatchClause {
+            kind,
+            binding,
+            body,
+        }),
+        _ => Err(LanaErr::Reason(
+            "Expected catch clause to bind the error to a symbol".into(),
+        )),
+    }
+}
+
+/// Evaluates `forms` like `do`, but outside of tail position.
+fn eval_body(forms: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    match eval_do_args(forms, env)? {
+        Evaluated::Value(value) => Ok(value),
+        Evaluated::TailCall(expr, env) => eval(expr, &env),
+        Evaluated::Expanded(expr, env) => eval(&expr, &env),
+        Evaluated::Call(expr, env, frame, file) => {
+            eval(&expr, &env).map_err(|err| raised_in(err, file.as_ref()).with_frame(frame))
+        }
+    }
+}
+
+fn eval_do_args<'a>(args: &'a [LanaExpr], env: &LanaEnv) -> Result<Evaluated<'a>, LanaErr> {
+    let (last, init) = match args.split_last() {
+        Some(split) => split,
+        None => return Ok(Evaluated::Value(LanaExpr::Nil)),
+    };
 
-        let result = eval(&expr, &mut env).expect("Could not eval if macro");
+    for expr in init {
+        eval(expr, env)?;
+    }
+
+    Ok(Evaluated::TailCall(last, env.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{SrcLocation, Tokenizer};
+    use crate::parser::parse_all;
+
+    /// Evaluates `input`, leaving out where errors were raised and their stack trace. See `eval_str_located`.
+    fn eval_str(input: &str, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+        eval_str_located(input, env).map_err(LanaErr::without_context)
+    }
+
+    fn eval_str_located(input: &str, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+        let tokens = Tokenizer::new(input).tokens();
+        let mut result = LanaExpr::Nil;
+
+        for expr in parse_all(&tokens)? {
+            result = eval(&expr, env)?;
+        }
 
-        assert_eq!(LanaExpr::Number(2.0), result);
+        Ok(result)
     }
 
     #[test]
-    fn it_expects_true_to_be_truthy() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("if".into()),
-            LanaExpr::Bool(true),
-            LanaExpr::Number(1.0),
-            LanaExpr::Number(2.0),
-        ]);
-        let mut env = LanaEnv::default();
+    fn it_expects_nil_to_evaluate_to_itself() {
+        let expr = LanaExpr::Nil;
+
+        let result = eval(&expr, &LanaEnv::default());
 
-        let result = eval(&expr, &mut env).expect("Could not eval if macro");
+        assert_eq!(Ok(LanaExpr::Nil), result)
+    }
+
+    #[test]
+    fn it_expects_string_to_evaluate_to_itself() {
+        let expr = LanaExpr::String("hello world".into());
+
+        let result = eval(&expr, &LanaEnv::default());
+
+        assert_eq!(Ok(LanaExpr::String("hello world".into())), result)
+    }
+
+    #[test]
+    fn it_expects_keyword_to_evaluate_to_itself() {
+        let expr = LanaExpr::Keyword(":ok".into());
+
+        let result = eval(&expr, &LanaEnv::default());
+
+        assert_eq!(Ok(LanaExpr::Keyword(":ok".into())), result)
+    }
+
+    #[test]
+    fn it_expects_symbol_to_evaluate_to_its_value() {
+        let expr = LanaExpr::Symbol("my-var".into());
+        let env = LanaEnv::default();
+        env.define("my-var", LanaExpr::Nil);
+
+        let result = eval(&expr, &env);
+
+        assert_eq!(Ok(LanaExpr::Nil), result)
+    }
+
+    #[test]
+    fn it_errors_on_undefined_symbols() {
+        let expr = LanaExpr::Symbol("my-var".into());
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env);
+
+        assert_eq!(Err(LanaErr::UndefinedSymbol("my-var".to_string())), result)
+    }
+
+    #[test]
+    fn it_expects_macro_do_to_return_nil_if_no_args_are_given() {
+        let expr = LanaExpr::List(vec![LanaExpr::Symbol("do".into())], None);
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env);
+
+        assert_eq!(Ok(LanaExpr::Nil), result)
+    }
+
+    #[test]
+    fn it_expects_do_macro_to_eval_multiple_exprs() {
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("do".into()),
+                LanaExpr::List(
+                    vec![
+                        LanaExpr::Symbol("def".into()),
+                        LanaExpr::Symbol("var1".into()),
+                        LanaExpr::int(1),
+                    ],
+                    None,
+                ),
+                LanaExpr::List(
+                    vec![
+                        LanaExpr::Symbol("def".into()),
+                        LanaExpr::Symbol("var2".into()),
+                        LanaExpr::int(2),
+                    ],
+                    None,
+                ),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+        env.define("var1", LanaExpr::int(0));
+        env.define("var2", LanaExpr::int(0));
+
+        eval(&expr, &env).ok();
+
+        assert_eq!(LanaExpr::int(1), env.get("var1").unwrap());
+        assert_eq!(LanaExpr::int(2), env.get("var2").unwrap());
+    }
+
+    #[test]
+    fn it_expects_do_macro_to_return_last_eval() {
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("do".into()),
+                LanaExpr::Bool(true),
+                LanaExpr::Bool(false),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env).expect("Could not eval do macro");
+
+        assert_eq!(LanaExpr::Bool(false), result);
+    }
+
+    #[test]
+    fn it_expects_nil_to_be_falsey() {
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("if".into()),
+                LanaExpr::Nil,
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env).expect("Could not eval if macro");
+
+        assert_eq!(LanaExpr::int(2), result);
+    }
+
+    #[test]
+    fn it_expects_false_to_be_falsey() {
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("if".into()),
+                LanaExpr::Bool(false),
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env).expect("Could not eval if macro");
+
+        assert_eq!(LanaExpr::int(2), result);
+    }
 
-        assert_eq!(LanaExpr::Number(1.0), result);
+    #[test]
+    fn it_expects_true_to_be_truthy() {
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("if".into()),
+                LanaExpr::Bool(true),
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env).expect("Could not eval if macro");
+
+        assert_eq!(LanaExpr::int(1), result);
     }
 
     #[test]
     fn it_expects_numbers_to_be_truthy() {
-        let expr = LanaExpr::List(vec![
-            LanaExpr::Symbol("if".into()),
-            LanaExpr::Number(0.0),
-            LanaExpr::Number(1.0),
-            LanaExpr::Number(2.0),
-        ]);
-        let mut env = LanaEnv::default();
+        let expr = LanaExpr::List(
+            vec![
+                LanaExpr::Symbol("if".into()),
+                LanaExpr::int(0),
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+            ],
+            None,
+        );
+        let env = LanaEnv::default();
+
+        let result = eval(&expr, &env).expect("Could not eval if macro");
+
+        assert_eq!(LanaExpr::int(1), result);
+    }
+
+    #[test]
+    fn it_expects_lambdas_to_capture_their_defining_env() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn make-adder (x) (fn (y) (+ x y)))
+             (def add-2 (make-adder 2))
+             (add-2 40)",
+            &env,
+        );
 
-        let result = eval(&expr, &mut env).expect("Could not eval if macro");
+        assert_eq!(Ok(LanaExpr::int(42)), result);
+    }
+
+    #[test]
+    fn it_expects_free_variables_to_be_resolved_lexically() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def x :global)
+             (defn get-x () x)
+             (defn shadow-x (x) (get-x))
+             (shadow-x :local)",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":global".into())), result);
+    }
+
+    #[test]
+    fn it_expects_def_inside_lambdas_to_not_leak() {
+        let env = LanaEnv::default();
+
+        eval_str("((fn () (def inner 1)))", &env).expect("Could not eval lambda");
+
+        assert_eq!(None, env.get("inner"));
+    }
+
+    #[test]
+    fn it_expects_quote_to_return_its_argument_unevaluated() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("'(undefined-fn 1)", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![LanaExpr::Symbol("undefined-fn".into()), LanaExpr::int(1)],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_expects_quasiquote_to_unquote_and_splice() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(def xs '(2 3)) `(1 ~(+ 1 1) ~@xs)", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::int(1),
+                    LanaExpr::int(2),
+                    LanaExpr::int(2),
+                    LanaExpr::int(3),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_expects_quasiquote_to_walk_collections() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def x 1) (def xs [2 3]) [`[~x ~@xs] `{:a ~x} `#{~@xs}]",
+            &env,
+        );
+
+        let mut map = IndexMap::new();
+        map.insert(LanaExpr::Keyword(":a".into()), LanaExpr::int(1));
+        let set = vec![LanaExpr::int(2), LanaExpr::int(3)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Vector(vec![LanaExpr::int(1), LanaExpr::int(2), LanaExpr::int(3)]),
+                LanaExpr::Map(map),
+                LanaExpr::Set(set),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_expects_macros_to_receive_unevaluated_args() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defmacro unless (condition then else) `(if ~condition ~else ~then))
+             (unless false :then (undefined-fn))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":then".into())), result);
+    }
+
+    #[test]
+    fn it_expands_macros_once_with_macroexpand_1() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defmacro my-if (c a b) `(if ~c ~a ~b))
+             (defmacro my-when (c body) `(my-if ~c ~body nil))
+             (macroexpand-1 '(my-when true 1))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::Symbol("my-if".into()),
+                    LanaExpr::Bool(true),
+                    LanaExpr::int(1),
+                    LanaExpr::Nil,
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_expands_macros_fully_with_macroexpand() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defmacro my-if (c a b) `(if ~c ~a ~b))
+             (defmacro my-when (c body) `(my-if ~c ~body nil))
+             (macroexpand '(my-when true 1))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::Symbol("if".into()),
+                    LanaExpr::Bool(true),
+                    LanaExpr::int(1),
+                    LanaExpr::Nil,
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_generates_unique_symbols() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(= (gensym \"x\") (gensym \"x\"))", &env);
+
+        assert_eq!(Ok(LanaExpr::Bool(false)), result);
+    }
+
+    #[test]
+    fn it_defines_core_functions_in_lana() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "[(inc 1) (dec 1) (not nil) (identity :x) ((comp inc inc) 1) ((partial + 1 2) 3)]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(2),
+                LanaExpr::int(0),
+                LanaExpr::Bool(true),
+                LanaExpr::Keyword(":x".into()),
+                LanaExpr::int(3),
+                LanaExpr::int(6),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_short_circuits_and_and_or() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"[(and) (and 1 2) (and 1 nil (throw (ex-info "not evaluated")))
+                (or) (or nil false) (or false 2 (throw (ex-info "not evaluated")))]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Bool(true),
+                LanaExpr::int(2),
+                LanaExpr::Nil,
+                LanaExpr::Nil,
+                LanaExpr::Bool(false),
+                LanaExpr::int(2),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_evaluates_the_first_matching_cond_clause() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn size (x) (cond (< x 1) :small (< x 10) :medium :else :large))
+             [(size 0) (size 5) (size 50) (cond false 1)]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Keyword(":small".into()),
+                LanaExpr::Keyword(":medium".into()),
+                LanaExpr::Keyword(":large".into()),
+                LanaExpr::Nil,
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_unpaired_cond_clauses() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(try (cond false 1 :else) (catch e (ex-message e)))", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::String(
+                "Expected an even number of forms in cond".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_evaluates_when_and_when_not_bodies() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def x 5)
+             [(when (> x 1) (def y 1) :big) (when (< x 1) :small) (when-not (< x 1) :not-small) y]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Keyword(":big".into()),
+                LanaExpr::Nil,
+                LanaExpr::Keyword(":not-small".into()),
+                LanaExpr::int(1),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_dispatches_on_case_constants() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(defn describe (x) (case x :a "keyword" 1 "one" "s" "string" (2 3) "two or three" "other"))
+               (map describe [:a 1 "s" 3 :z])"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::String("keyword".into()),
+                    LanaExpr::String("one".into()),
+                    LanaExpr::String("string".into()),
+                    LanaExpr::String("two or three".into()),
+                    LanaExpr::String("other".into()),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_when_no_case_clause_matches() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(case :c :a 1 :b 2)", &env);
+
+        assert_eq!(
+            Err(LanaErr::Reason(
+                "No case clause matches keyword ':c'".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_points_errors_in_core_functions_at_their_call_site() {
+        let env = LanaEnv::default();
+
+        let result = eval_str_located("(def x 1)\n  (inc \"one\")", &env);
+
+        assert_eq!(Some(span((2, 3), (2, 13))), result.unwrap_err().span());
+    }
+
+    #[test]
+    fn it_frees_frames_holding_the_lambdas_that_capture_them() {
+        use std::cell::RefCell;
+        use std::rc::Weak;
+
+        let env = LanaEnv::default();
+        let tracked: Rc<RefCell<Vec<Weak<LanaAtom>>>> = Rc::default();
+        let track = tracked.clone();
+
+        env.register(NativeFn::new("track", move |_args| {
+            let atom = Rc::new(LanaAtom::new(LanaExpr::Nil));
+            track.borrow_mut().push(Rc::downgrade(&atom));
+
+            Ok(LanaExpr::Atom(atom))
+        }));
+        let result = eval_str(
+            "(defn f (x) (let (a (track) g (fn (y) a)) (g x)))
+             (defn h () (do (defn inner () (track)) (inner)))
+             (defn run (n) (if (> n 0) (do (f n) (h) (run (- n 1))) :done))
+             (run 100)",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":done".into())), result);
+        assert_eq!(200, tracked.borrow().len());
+        assert!(tracked.borrow().iter().all(|atom| atom.upgrade().is_none()));
+    }
+
+    #[test]
+    fn it_keeps_frames_of_lambdas_that_escape_them() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn make () (let (x 1 g (fn () x)) g))
+             (def made (make))
+             (made)",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::int(1)), result);
+    }
 
-        assert_eq!(LanaExpr::Number(1.0), result);
+    #[test]
+    fn it_frees_the_core_environment_when_dropped() {
+        let env = LanaEnv::default();
+        let marker = Rc::new(());
+        let freed = Rc::downgrade(&marker);
+
+        env.root().register(NativeFn::new("marker", move |_args| {
+            Ok(LanaExpr::int(Rc::strong_count(&marker) as i64))
+        }));
+        // Core functions still work while the environment is alive
+        assert_eq!(Ok(LanaExpr::int(2)), eval_str("((comp inc inc) 0)", &env));
+        drop(env);
+
+        assert!(freed.upgrade().is_none());
+    }
+
+    #[test]
+    fn it_does_not_grow_the_stack_on_tail_calls() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn count-down (n) (if (<= n 0) :done (do n (count-down (- n 1)))))
+             (count-down 20000)",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":done".into())), result);
+    }
+
+    #[test]
+    fn it_does_not_grow_the_stack_on_mutually_recursive_tail_calls() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn even? (n) (if (= n 0) true (odd? (- n 1))))
+             (defn odd? (n) (if (= n 0) false (even? (- n 1))))
+             (even? 20001)",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Bool(false)), result);
+    }
+
+    #[test]
+    fn it_expects_let_bindings_to_be_sequential() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let (a 1 b (+ a 1)) (+ a b))", &env);
+
+        assert_eq!(Ok(LanaExpr::int(3)), result);
+    }
+
+    #[test]
+    fn it_accepts_let_bindings_in_a_vector() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let [a 1 b (+ a 1)] (+ a b))", &env);
+
+        assert_eq!(Ok(LanaExpr::int(3)), result);
+    }
+
+    #[test]
+    fn it_expects_let_bindings_to_not_leak() {
+        let env = LanaEnv::default();
+
+        eval_str("(let (a 1) a)", &env).expect("Could not eval let");
+
+        assert_eq!(None, env.get("a"));
+    }
+
+    #[test]
+    fn it_expects_let_to_destructure_lists() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let ((a (b c) d) '(1 (2 3))) `(~a ~b ~c ~d))", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::int(1),
+                    LanaExpr::int(2),
+                    LanaExpr::int(3),
+                    LanaExpr::Nil,
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_expects_lambda_params_to_destructure_lists() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("((fn ((a b) c) (+ a b c)) '(1 2) 3)", &env);
+
+        assert_eq!(Ok(LanaExpr::int(6)), result);
+    }
+
+    #[test]
+    fn it_errors_when_destructuring_non_lists() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let ((a b) 1) a)", &env);
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Cannot destructure number '1' as a sequence".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_binds_rest_params_to_a_list() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("((fn (x & more) `(~x ~more)) 1 2 3)", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::int(1),
+                    LanaExpr::List(vec![LanaExpr::int(2), LanaExpr::int(3)], None),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_binds_missing_rest_params_to_an_empty_list() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("((fn (x & more) more) 1)", &env);
+
+        assert_eq!(Ok(LanaExpr::List(vec![], None)), result);
+    }
+
+    #[test]
+    fn it_destructures_rest_elements_in_let() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let ((a & tail) '(1 2 3)) tail)", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![LanaExpr::int(2), LanaExpr::int(3)],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_dispatches_multi_arity_functions_by_argument_count() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn greet
+               (() (greet \"world\"))
+               ((who) who)
+               ((who & others) others))
+             `(~(greet) ~(greet :a) ~(greet :a :b))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::String("world".into()),
+                    LanaExpr::Keyword(":a".into()),
+                    LanaExpr::List(vec![LanaExpr::Keyword(":b".into())], None),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_merges_overlapping_arities_in_arity_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn f ((x y & z) y) ((x) x) ((x y) y) ((x y z) z)) (f)",
+            &env,
+        );
+
+        assert_eq!(
+            Err(LanaErr::ArityError(
+                "'f' expected 1 or at least 2 argument(s), got 0".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_names_the_function_on_arity_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(defn f ((x) x) ((x y & z) y)) (f)", &env);
+
+        assert_eq!(
+            Err(LanaErr::ArityError(
+                "'f' expected 1 or at least 2 argument(s), got 0".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_malformed_rest_params() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(fn (x & y z) x)", &env);
+
+        assert_eq!(
+            Err(LanaErr::Reason(
+                "Expected exactly one pattern after '&'".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_evaluates_collection_literals() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("{:sum (+ 1 2) :items [(+ 1 1) #{:a}]}", &env);
+
+        let mut set = IndexSet::new();
+        set.insert(LanaExpr::Keyword(":a".into()));
+        let mut map = IndexMap::new();
+        map.insert(LanaExpr::Keyword(":sum".into()), LanaExpr::int(3));
+        map.insert(
+            LanaExpr::Keyword(":items".into()),
+            LanaExpr::Vector(vec![LanaExpr::int(2), LanaExpr::Set(set)]),
+        );
+        assert_eq!(Ok(LanaExpr::Map(map)), result);
+    }
+
+    #[test]
+    fn it_expects_maps_to_be_compared_structurally() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(get {{:a [1 2]} :found} {:a '(1 2)})", &env);
+
+        assert_eq!(Ok(LanaExpr::Keyword(":found".into())), result);
+    }
+
+    #[test]
+    fn it_destructures_maps_and_vectors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(let ({name :name [x y] :pos} {:name :p :pos [1 2]}) [name x y])",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Keyword(":p".into()),
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_updates_collections_without_mutating_them() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def config {:host :localhost})
+             (def updated (dissoc (assoc config :port 80 :debug true) :host))
+             [(count config) (keys updated) (conj [1] 2) (conj '(1) 2) (contains? #{:a} :a)]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(1),
+                LanaExpr::List(
+                    vec![
+                        LanaExpr::Keyword(":port".into()),
+                        LanaExpr::Keyword(":debug".into())
+                    ],
+                    None
+                ),
+                LanaExpr::Vector(vec![LanaExpr::int(1), LanaExpr::int(2)]),
+                LanaExpr::List(vec![LanaExpr::int(2), LanaExpr::int(1)], None),
+                LanaExpr::Bool(true),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_calls_lambdas_and_builtins_from_higher_order_functions() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defn square (x) (* x x))
+             (reduce + 0 (filter (fn (x) (> x 10)) (map square (range 1 6))))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::int(41)), result);
+    }
+
+    #[test]
+    fn it_applies_functions_to_argument_lists() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(apply + 1 2 '(3 4))", &env);
+
+        assert_eq!(Ok(LanaExpr::int(10)), result);
+    }
+
+    #[test]
+    fn it_builds_and_slices_lists() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(list (first '(1 2)) (rest [1 2]) (cons 0 [1]) (nth '(1 2) 1) (take 2 (drop 1 (range 5))))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::int(1),
+                    LanaExpr::List(vec![LanaExpr::int(2)], None),
+                    LanaExpr::List(vec![LanaExpr::int(0), LanaExpr::int(1)], None),
+                    LanaExpr::int(2),
+                    LanaExpr::List(vec![LanaExpr::int(1), LanaExpr::int(2)], None),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_sorts_sequences() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(list (sort [3 1 2]) (sort-by (fn (p) (get p :age)) [{:age 30} {:age 20}]))",
+            &env,
+        );
+
+        let person = |age| {
+            let mut map = IndexMap::new();
+            map.insert(LanaExpr::Keyword(":age".into()), LanaExpr::int(age));
+            LanaExpr::Map(map)
+        };
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::List(
+                        vec![LanaExpr::int(1), LanaExpr::int(2), LanaExpr::int(3)],
+                        None
+                    ),
+                    LanaExpr::List(vec![person(20), person(30)], None),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_when_sorting_incomparable_values() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(sort [1 :a])", &env);
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Cannot compare keyword ':a' with number '1'".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_catches_builtin_errors_by_kind() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try
+               (+ 1 \"2\")
+               (catch :arity-error e :arity)
+               (catch :type-error e (ex-message e)))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::String(
+                "Expected a number, got string \"2\"".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_catches_parse_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try (num \"forty-two\") (catch :parse-error e :parse))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":parse".into())), result);
+    }
+
+    #[test]
+    fn it_catches_index_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "[(try (nth [1 2] 2) (catch :index-error e :nth))
+              (try (assoc [1 2] 3 0) (catch :index-error e :assoc))
+              (try (subs \"ab\" 1 3) (catch :index-error e :substring))]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Keyword(":nth".into()),
+                LanaExpr::Keyword(":assoc".into()),
+                LanaExpr::Keyword(":substring".into()),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_catches_arithmetic_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try (/ 1 0) (catch :arithmetic-error e (ex-message e)))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::String("Divide by zero".into())), result);
+    }
+
+    #[test]
+    fn it_catches_thrown_errors_with_their_data() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try
+               (throw (ex-info \"boom\" {:code 42}))
+               (catch e [(ex-kind e) (get (ex-data e) :code)]))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Keyword(":ex-info".into()),
+                LanaExpr::int(42)
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_rethrows_errors_without_a_matching_catch_clause() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(try undefined-var (catch :type-error e nil))", &env);
+
+        assert_eq!(
+            Err(LanaErr::UndefinedSymbol("undefined-var".into())),
+            result
+        );
+    }
+
+    #[test]
+    fn it_always_evaluates_finally_clauses() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try (throw (ex-info \"boom\" nil)) (finally (def cleaned-up true)))",
+            &env,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(Some(LanaExpr::Bool(true)), env.get("cleaned-up"));
+    }
+
+    #[test]
+    fn it_returns_the_body_value_when_nothing_is_thrown() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(try 1 2 (catch e 3) (finally 4))", &env);
+
+        assert_eq!(Ok(LanaExpr::int(2)), result);
+    }
+
+    fn span(start: (i32, i32), end: (i32, i32)) -> SrcSpan {
+        SrcSpan::new(
+            SrcLocation::new(start.0, start.1),
+            SrcLocation::new(end.0, end.1),
+        )
+    }
+
+    #[test]
+    fn it_points_errors_at_the_argument_that_raised_them() {
+        let env = LanaEnv::default();
+
+        let result = eval_str_located("(+ 1\n   undefined-var)", &env);
+
+        assert_eq!(
+            Err(LanaErr::Located(
+                Box::new(LanaErr::UndefinedSymbol("undefined-var".into())),
+                span((2, 4), (2, 16))
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_points_errors_at_the_innermost_form_that_raised_them() {
+        let env = LanaEnv::default();
+
+        let result = eval_str_located("(defn add (a b)\n  (+ a b))\n(add 1 \"two\")", &env);
+
+        assert_eq!(Some(span((2, 3), (2, 9))), result.unwrap_err().span());
+    }
+
+    #[test]
+    fn it_remembers_where_caught_errors_were_raised() {
+        let env = LanaEnv::default();
+
+        let result = eval_str_located("(try\n  (/ 1 :a)\n  (catch e e))", &env);
+
+        match result {
+            Ok(LanaExpr::Error(error)) => assert_eq!(Some(span((2, 3), (2, 10))), error.span),
+            other => panic!("Expected an error value, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_points_errors_inside_interpolated_strings() {
+        let env = LanaEnv::default();
+
+        let result = eval_str_located("(def n 1)\n$\"n is #{(+ n :a)}\"", &env);
+
+        assert_eq!(Some(span((2, 10), (2, 17))), result.unwrap_err().span());
+    }
+
+    fn frame_names(err: &LanaErr) -> Vec<Option<String>> {
+        err.stack_trace()
+            .iter()
+            .map(|frame| frame.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn it_records_the_functions_errors_propagate_out_of() {
+        let env = LanaEnv::default();
+
+        let err = eval_str_located(
+            "(defn inner () (+ 1 :a))\n(defn outer () (do (inner) nil))\n(outer)",
+            &env,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            vec![Some("inner".to_string()), Some("outer".to_string())],
+            frame_names(&err)
+        );
+        assert_eq!(Some(span((2, 20), (2, 26))), err.stack_trace()[0].call_site);
+    }
+
+    #[test]
+    fn it_records_a_frame_per_recursive_call() {
+        let env = LanaEnv::default();
+
+        let err = eval_str_located(
+            "(defn fact (n) (if (= n 0) (throw (ex-info \"boom\")) (* n (fact (- n 1)))))
+             (fact 3)",
+            &env,
+        )
+        .unwrap_err();
+
+        assert_eq!(vec![Some("fact".to_string()); 4], frame_names(&err));
+    }
+
+    #[test]
+    fn it_replaces_the_callers_frame_on_tail_calls() {
+        let env = LanaEnv::default();
+
+        let err = eval_str_located(
+            "(defn count-down (n) (if (= n 0) undefined-var (count-down (- n 1))))
+             (count-down 1000)",
+            &env,
+        )
+        .unwrap_err();
+
+        assert_eq!(vec![Some("count-down".to_string())], frame_names(&err));
+    }
+
+    #[test]
+    fn it_records_functions_called_from_builtins_without_a_call_site() {
+        let env = LanaEnv::default();
+
+        let err = eval_str_located("(map (fn (x) (+ x :a)) '(1))", &env).unwrap_err();
+
+        assert_eq!(
+            vec![StackFrame {
+                name: None,
+                call_site: None
+            }],
+            err.stack_trace()
+        );
+    }
+
+    #[test]
+    fn it_promotes_integers_to_bignums_on_overflow() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(- (* 9223372036854775807 2) 9223372036854775807)", &env);
+        let big = eval_str("(+ 9223372036854775807 1)", &env);
+
+        assert_eq!(Ok(LanaExpr::int(i64::MAX)), result);
+        assert_eq!("9223372036854775808", big.unwrap().to_string());
+    }
+
+    #[test]
+    fn it_divides_integers_into_ratios() {
+        let env = LanaEnv::default();
+
+        assert_eq!("1/3", eval_str("(/ 1 3)", &env).unwrap().to_string());
+        assert_eq!(Ok(LanaExpr::int(2)), eval_str("(/ 6 3)", &env));
+        assert_eq!(Ok(LanaExpr::int(1)), eval_str("(+ 1/3 2/3)", &env));
+    }
+
+    #[test]
+    fn it_makes_floats_contagious() {
+        let env = LanaEnv::default();
+
+        assert_eq!(Ok(LanaExpr::float(1.0)), eval_str("(+ 1/2 0.5)", &env));
+        assert_eq!(Ok(LanaExpr::float(3.0)), eval_str("(* 2 1.5)", &env));
+    }
+
+    #[test]
+    fn it_compares_numbers_across_the_tower() {
+        let env = LanaEnv::default();
+
+        assert_eq!(Ok(LanaExpr::Bool(true)), eval_str("(== 1 1.0 2/2)", &env));
+        assert_eq!(Ok(LanaExpr::Bool(true)), eval_str("(< 1/3 0.34 1)", &env));
+        assert_eq!(Ok(LanaExpr::Bool(false)), eval_str("(== 1/3 0.33)", &env));
+        assert!(matches!(
+            eval_str("(== 1 :a)", &env),
+            Err(LanaErr::TypeError(_))
+        ));
+    }
+
+    #[test]
+    fn it_errors_on_exact_division_by_zero() {
+        let env = LanaEnv::default();
+
+        assert_eq!(
+            Err(LanaErr::ArithmeticError("Divide by zero".into())),
+            eval_str("(/ 1 0)", &env)
+        );
+        assert_eq!(
+            Ok(LanaExpr::float(f64::INFINITY)),
+            eval_str("(/ 1.0 0)", &env)
+        );
+    }
+
+    #[test]
+    fn it_compares_any_values_for_equality() {
+        let env = LanaEnv::default();
+
+        let equal = eval_str(
+            r#"(list (= "a" "a") (= :a :a) (= '(1 "b") [1 "b"]) (= {:a [1]} {:a [1]}))"#,
+            &env,
+        );
+        let not_equal = eval_str(r#"(list (not= "a" "b") (not= :a "a") (= 1 nil))"#, &env);
+
+        let all = |b| Ok(LanaExpr::List(vec![LanaExpr::Bool(b); 4], None));
+        assert_eq!(all(true), equal);
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::Bool(true),
+                    LanaExpr::Bool(true),
+                    LanaExpr::Bool(false)
+                ],
+                None
+            )),
+            not_equal
+        );
+    }
+
+    #[test]
+    fn it_compares_numbers_by_type_and_value_for_equality() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(list (= 1 1.0) (= [1] [1.0]) (= #{1} #{1.0}) (contains? {1 :a} 1.0) (= 1/2 1/2))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::Bool(false),
+                    LanaExpr::Bool(false),
+                    LanaExpr::Bool(false),
+                    LanaExpr::Bool(false),
+                    LanaExpr::Bool(true),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_orders_strings_and_sequences() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(list (< "a" "b" "c") (> "b" "a") (compare [1 2] [1 3]) (compare :b :a) (sort ["b" "c" "a"]))"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::Bool(true),
+                    LanaExpr::Bool(true),
+                    LanaExpr::int(-1),
+                    LanaExpr::int(1),
+                    LanaExpr::List(
+                        vec![
+                            LanaExpr::String("a".into()),
+                            LanaExpr::String("b".into()),
+                            LanaExpr::String("c".into())
+                        ],
+                        None
+                    ),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_when_ordering_values_of_different_types() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(r#"(< 1 "a")"#, &env);
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Cannot compare number '1' with string \"a\"".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_builds_strings() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(list (str "a" 1 nil :b) (subs "héllo" 1 3) (str/join ", " (str/split "a-b" "-")))"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::List(
+                vec![
+                    LanaExpr::String("a1:b".into()),
+                    LanaExpr::String("él".into()),
+                    LanaExpr::String("a, b".into()),
+                ],
+                None
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_searches_and_transforms_strings() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"[(str/upper-case (str/trim " hi ")) (str/replace "a.b.c" "." "/")
+                (str/starts-with? "lana" "la") (str/includes? "lana" "x") (str/index-of "héllo" "l")]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::String("HI".into()),
+                LanaExpr::String("a/b/c".into()),
+                LanaExpr::Bool(true),
+                LanaExpr::Bool(false),
+                LanaExpr::int(2),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_counts_strings_by_character_and_grapheme() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "[(str/char-count \"e\u{301}\") (str/grapheme-count \"e\u{301}\")]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![LanaExpr::int(2), LanaExpr::int(1)])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_evaluates_forms_embedded_in_interpolated_strings() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r##"(def n 3) (defn twice (x) (* x 2)) $"#{n} twice is #{(twice n)}, not \#{n}""##,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::String("3 twice is 6, not #{n}".into())),
+            result
+        );
+    }
+
+    #[test]
+    fn it_interpolates_strings_where_str_is_shadowed() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(r#"(defn greet (str) $"hello #{str}") (greet "you")"#, &env);
+
+        assert_eq!(Ok(LanaExpr::String("hello you".into())), result);
+    }
+
+    #[test]
+    fn it_formats_strings() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(format "%s: %5.2f|%-4d|%04x|%+d|%e|%%" "pi" 3.14159 7 255 1 1500)"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::String(
+                "pi:  3.14|7   |00ff|+1|1.500000e+03|%".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_missing_format_arguments() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(r#"(format "%s and %s" "a")"#, &env);
+
+        assert_eq!(
+            Err(LanaErr::Reason(
+                "Not enough arguments for format directive '%s'".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_finds_regex_matches() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"[(re-find #"\d+" "ab 12 34") (re-find #"(\w)(\d)?" "a") (re-seq #"\d" "1a2")
+                (re-matches #"a|ab" "ab") (re-matches #"\d+" "12x")]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::String("12".into()),
+                LanaExpr::Vector(vec![
+                    LanaExpr::String("a".into()),
+                    LanaExpr::String("a".into()),
+                    LanaExpr::Nil
+                ]),
+                LanaExpr::List(
+                    vec![LanaExpr::String("1".into()), LanaExpr::String("2".into())],
+                    None
+                ),
+                LanaExpr::String("ab".into()),
+                LanaExpr::Nil,
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_compiles_anchored_regexes_once() {
+        let env = LanaEnv::default();
+        eval_str(r#"(def r #"a|ab") (re-matches r "ab")"#, &env).unwrap();
+
+        let regex = match env.get("r") {
+            Some(LanaExpr::Regex(regex)) => regex,
+            other => panic!("Expected a regex, got {:?}", other),
+        };
+        let anchored = regex.anchored() as *const _;
+        eval_str(r#"(re-matches r "a")"#, &env).unwrap();
+
+        assert_eq!(anchored, regex.anchored() as *const _);
+    }
+
+    #[test]
+    fn it_returns_named_regex_groups_as_maps() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(r#"(re-find #"(?P<key>\w+)=(?P<value>\w+)" "a=1")"#, &env);
+
+        let mut map = IndexMap::new();
+        map.insert(
+            LanaExpr::Keyword(":key".into()),
+            LanaExpr::String("a".into()),
+        );
+        map.insert(
+            LanaExpr::Keyword(":value".into()),
+            LanaExpr::String("1".into()),
+        );
+        assert_eq!(Ok(LanaExpr::Map(map)), result);
+    }
+
+    #[test]
+    fn it_replaces_and_splits_with_regexes() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"[(re-replace #"(\d+)" "a1b22" "<$1>") (re-replace #"\d+" "a1b22" (fn (m) (str (count m))))
+                (re-split #"\s*,\s*" "a , b,c")]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::String("a<1>b<22>".into()),
+                LanaExpr::String("a1b2".into()),
+                LanaExpr::Vector(vec![
+                    LanaExpr::String("a".into()),
+                    LanaExpr::String("b".into()),
+                    LanaExpr::String("c".into())
+                ]),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_invalid_regexes_when_reading_them() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(r#"(println "never printed") #"(""#, &env);
+
+        assert!(
+            matches!(result, Err(LanaErr::Reason(message)) if message.starts_with("Invalid regex"))
+        );
+    }
+
+    /// A fresh directory for a test to write files to.
+    fn temp_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn it_reads_and_writes_files() {
+        let env = LanaEnv::default();
+        env.define(
+            "dir",
+            LanaExpr::String(temp_dir("lana-it-reads-and-writes-files")),
+        );
+
+        let result = eval_str(
+            r#"(mkdir dir)
+               (def file (path/join dir "notes.txt"))
+               (spit file "one\n")
+               (spit file "two\n" :append true)
+               [(slurp file) (read-lines file)]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::String("one\ntwo\n".into()),
+                LanaExpr::List(
+                    vec![
+                        LanaExpr::String("one".into()),
+                        LanaExpr::String("two".into())
+                    ],
+                    None
+                ),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_manages_files_and_directories() {
+        let env = LanaEnv::default();
+        env.define("dir", LanaExpr::String(temp_dir("lana-it-manages-files")));
+
+        let result = eval_str(
+            r#"(mkdir dir)
+               (spit (path/join dir "a.txt") "a")
+               (copy-file (path/join dir "a.txt") (path/join dir "b.txt"))
+               (def listed (map path/file-name (list-dir dir)))
+               (delete-file (path/join dir "a.txt"))
+               [listed (file-exists? (path/join dir "a.txt")) (slurp (path/join dir "b.txt"))]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::List(
+                    vec![
+                        LanaExpr::String("a.txt".into()),
+                        LanaExpr::String("b.txt".into())
+                    ],
+                    None
+                ),
+                LanaExpr::Bool(false),
+                LanaExpr::String("a".into()),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_splits_paths() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"[(path/file-name "a/b.txt") (path/parent "a/b.txt") (path/extension "a/b.txt") (path/extension "a/b")]"#,
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::String("b.txt".into()),
+                LanaExpr::String("a".into()),
+                LanaExpr::String("txt".into()),
+                LanaExpr::Nil,
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_catches_io_errors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(try (slurp "/lana/missing.txt") (catch e (ex-kind e)))"#,
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":io-error".into())), result);
+    }
+
+    #[test]
+    fn it_reports_both_paths_when_copying_fails() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(try (copy-file "/lana/missing.txt" "/lana/copy.txt") (catch :io-error e (ex-message e)))"#,
+            &env,
+        );
+
+        match result {
+            Ok(LanaExpr::String(message)) => assert!(
+                message.starts_with("Could not copy '/lana/missing.txt' to '/lana/copy.txt': "),
+                "{}",
+                message
+            ),
+            other => panic!("Expected an error message, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn it_parses_json() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            r#"(json/parse "{\"name\": \"lana\", \"tags\": [1, null]}")"#,
+            &env,
+        );
+
+        let mut map = IndexMap::new();
+        map.insert(
+            LanaExpr::String("name".into()),
+            LanaExpr::String("lana".into()),
+        );
+        map.insert(
+            LanaExpr::String("tags".into()),
+            LanaExpr::Vector(vec![LanaExpr::int(1), LanaExpr::Nil]),
+        );
+        assert_eq!(Ok(LanaExpr::Map(map)), result);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn it_stringifies_json() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(json/stringify {:a '(1 \"two\" nil) :b 1.5})", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::String(r#"{"a":[1,"two",null],"b":1.5}"#.into())),
+            result
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn it_errors_on_invalid_json() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try (json/parse \"{\") (catch :parse-error e (ex-message e)))",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::String(
+                "Invalid JSON: EOF while parsing an object at line 1 column 1".into()
+            )),
+            result
+        );
+    }
+
+    /// Writes `files`, given as paths relative to a fresh directory and their content, and returns
+    /// the path of the first one.
+    fn write_program(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::path::PathBuf::from(temp_dir(name));
+
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+
+        dir.join(files[0].0)
+    }
+
+    #[test]
+    fn it_loads_files_relative_to_the_current_one() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-loads-files",
+            &[
+                ("main.lana", r#"(load "lib/math.lana") (double 21)"#),
+                (
+                    "lib/math.lana",
+                    r#"(load "helpers.lana") (defn double (x) (twice * x))"#,
+                ),
+                ("lib/helpers.lana", "(defn twice (f x) (f x 2))"),
+            ],
+        );
+
+        let result = eval_file(&main, &env);
+
+        assert_eq!(Ok(LanaExpr::int(42)), result);
+    }
+
+    #[test]
+    fn it_points_errors_at_the_file_that_raised_them() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-points-errors-at-files",
+            &[
+                ("main.lana", "(load \"lib.lana\")\n(f 1)"),
+                ("lib.lana", "(defn f (x)\n  (+ x :a))"),
+            ],
+        );
+        let lib: Rc<Path> = Rc::from(main.with_file_name("lib.lana"));
+        let main: Rc<Path> = Rc::from(main);
+
+        let error = eval_file(&main, &env).unwrap_err();
+
+        assert_eq!(Some(span((2, 3), (2, 10)).in_file(&lib)), error.span());
+        assert_eq!(
+            &[StackFrame {
+                name: Some("f".into()),
+                call_site: Some(span((2, 1), (2, 5)).in_file(&main)),
+            }],
+            error.stack_trace()
+        );
+    }
+
+    #[test]
+    fn it_requires_modules_with_aliases_and_referred_names() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-requires-modules",
+            &[
+                (
+                    "main.lana",
+                    r#"(require '[geometry.shapes :as shapes :refer [square]])
+                       [(shapes/area 3) (square 2) (geometry.shapes/area 1) shapes/sides]"#,
+                ),
+                (
+                    "geometry/shapes.lana",
+                    "(ns geometry.shapes)
+                     (def sides 4)
+                     (defn square (x) (* x x))
+                     (defn area (side) (square side))",
+                ),
+            ],
+        );
+
+        let result = eval_file(&main, &env);
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(9),
+                LanaExpr::int(4),
+                LanaExpr::int(1),
+                LanaExpr::int(4)
+            ])),
+            result
+        );
+        assert_eq!(None, env.get("area"));
+    }
+
+    #[test]
+    fn it_requires_modules_in_ns_declarations() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-requires-modules-in-ns",
+            &[
+                (
+                    "main.lana",
+                    "(ns app (:require [greetings :as g] [numbers :refer (one)]))
+                     (g/hello one)",
+                ),
+                ("greetings.lana", r#"(defn hello (n) (str "hello " n))"#),
+                ("numbers.lana", "(def one 1)"),
+            ],
+        );
+
+        let result = eval_file(&main, &env);
+
+        assert_eq!(Ok(LanaExpr::String("hello 1".into())), result);
+    }
+
+    #[test]
+    fn it_evaluates_each_module_once() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-evaluates-modules-once",
+            &[
+                ("main.lana", "(require 'counter 'first 'second) (slurp log)"),
+                ("first.lana", "(require 'counter)"),
+                ("second.lana", "(ns second (:require counter))"),
+                ("counter.lana", r#"(spit log "loaded\n" :append true)"#),
+            ],
+        );
+        let log = main.with_file_name("log").to_string_lossy().into_owned();
+        // Modules only see the prelude
+        env.root().define("log", LanaExpr::String(log));
+
+        let result = eval_file(&main, &env);
+
+        assert_eq!(Ok(LanaExpr::String("loaded\n".into())), result);
+    }
+
+    #[test]
+    fn it_looks_for_modules_in_the_search_path() {
+        let main = write_program(
+            "lana-it-looks-for-modules-in-lana-path",
+            &[
+                (
+                    "app/main.lana",
+                    "(require '[vendored.lib :as lib]) (lib/answer)",
+                ),
+                ("vendor/vendored/lib.lana", "(defn answer () 42)"),
+            ],
+        );
+        let vendor = main.parent().unwrap().parent().unwrap().join("vendor");
+        let env = LanaEnv::with_modules(modules::Modules::new(vec![vendor]));
+
+        let result = eval_file(&main, &env);
+
+        assert_eq!(Ok(LanaExpr::int(42)), result);
+    }
+
+    #[test]
+    fn it_errors_on_circular_requires() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-errors-on-circular-requires",
+            &[
+                ("main.lana", "(require 'a)"),
+                ("a.lana", "(ns a (:require b))"),
+                ("b.lana", "(ns b (:require a))"),
+            ],
+        );
+
+        let result = eval_file(&main, &env).map_err(LanaErr::without_context);
+
+        assert_eq!(
+            Err(LanaErr::Reason("Circular require: a -> b -> a".into())),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_missing_modules() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(try (require 'missing.module) (catch :io-error e :missing))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Keyword(":missing".into())), result);
+    }
+
+    #[test]
+    fn it_errors_when_modules_declare_another_namespace() {
+        let env = LanaEnv::default();
+        let main = write_program(
+            "lana-it-errors-on-mismatched-namespaces",
+            &[
+                ("main.lana", "(require 'utils)"),
+                ("utils.lana", "(ns util)"),
+            ],
+        );
+
+        let result = eval_file(&main, &env).map_err(LanaErr::without_context);
+
+        assert_eq!(
+            Err(LanaErr::Reason(
+                "Module 'utils' declares a different namespace, 'util'".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_updates_atoms() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def counter (atom 0))
+             (swap! counter + 10)
+             (swap! counter inc)
+             (def before @counter)
+             (reset! counter 1)
+             [before (deref counter) (compare-and-set! counter 2 3) (compare-and-set! counter 1 3) @counter]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(11),
+                LanaExpr::int(1),
+                LanaExpr::Bool(false),
+                LanaExpr::Bool(true),
+                LanaExpr::int(3),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_shares_atoms_between_copies() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def a (atom []))
+             (def b a)
+             (swap! b conj 1)
+             [@a (= a b) (= a (atom [1])) (str a)]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Vector(vec![LanaExpr::int(1)]),
+                LanaExpr::Bool(true),
+                LanaExpr::Bool(false),
+                LanaExpr::String("#atom[[1]]".into()),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_derefs_atoms_where_deref_is_shadowed() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(let (deref 1) @(atom 2))", &env);
+
+        assert_eq!(Ok(LanaExpr::int(2)), result);
+    }
+
+    #[test]
+    fn it_calls_atom_watchers_on_changes() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def changes (atom []))
+             (def counter (atom 0))
+             (add-watch counter :log (fn (key ref old new) (swap! changes conj [key old new])))
+             (swap! counter inc)
+             (reset! counter 5)
+             (remove-watch counter :log)
+             (reset! counter 6)
+             @changes",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Vector(vec![
+                    LanaExpr::Keyword(":log".into()),
+                    LanaExpr::int(0),
+                    LanaExpr::int(1)
+                ]),
+                LanaExpr::Vector(vec![
+                    LanaExpr::Keyword(":log".into()),
+                    LanaExpr::int(1),
+                    LanaExpr::int(5)
+                ]),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_rebinds_variables_in_their_scope_with_set() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(def total 0)
+             (defn add! (n) (set! total (+ total n)))
+             (add! 1)
+             (add! 2)
+             (let (total 10) (set! total 20))
+             total",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::int(3)), result);
+    }
+
+    #[test]
+    fn it_errors_when_setting_undefined_variables() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(set! missing 1)", &env);
+
+        assert_eq!(Err(LanaErr::UndefinedSymbol("missing".into())), result);
+    }
+
+    #[test]
+    fn it_defines_records() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defrecord Point (x y))
+             (def p (->Point 1 2))
+             [(:x p) (get p :y) (:z p :none) (point? p) (point? {:x 1 :y 2}) (type p) (str p)]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(1),
+                LanaExpr::int(2),
+                LanaExpr::Keyword(":none".into()),
+                LanaExpr::Bool(true),
+                LanaExpr::Bool(false),
+                LanaExpr::String("Point".into()),
+                LanaExpr::String("#Point{:x 1 :y 2}".into()),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_compares_records_by_type_and_values() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defrecord Point (x y))
+             (defrecord Size (x y))
+             [(= (->Point 1 2) (->Point 1 2)) (= (->Point 1 2) (->Point 2 1)) (= (->Point 1 2) (->Size 1 2))]",
+            &env,
+        );
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::Bool(true),
+                LanaExpr::Bool(false),
+                LanaExpr::Bool(false),
+            ])),
+            result
+        );
+    }
+
+    #[test]
+    fn it_names_predicates_of_records_in_kebab_case() {
+        let env = LanaEnv::default();
+
+        let result = eval_str(
+            "(defrecord TodoItem (title)) (todo-item? (->TodoItem \"write tests\"))",
+            &env,
+        );
+
+        assert_eq!(Ok(LanaExpr::Bool(true)), result);
+    }
+
+    #[test]
+    fn it_names_records_in_error_messages() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(defrecord Point (x y)) (+ 1 (->Point 1 2))", &env);
+
+        assert_eq!(
+            Err(LanaErr::TypeError(
+                "Expected a number, got Point '#Point{:x 1 :y 2}'".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_checks_the_arity_of_record_constructors() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("(defrecord Point (x y)) (->Point 1)", &env);
+
+        assert_eq!(
+            Err(LanaErr::ArityError(
+                "'->Point' expected 2 argument(s), got 1".into()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_looks_keywords_up_in_maps_when_called() {
+        let env = LanaEnv::default();
+
+        let result = eval_str("[(:a {:a 1}) (:b {:a 1}) (map :a [{:a 2} {:a 3}])]", &env);
+
+        assert_eq!(
+            Ok(LanaExpr::Vector(vec![
+                LanaExpr::int(1),
+                LanaExpr::Nil,
+                LanaExpr::List(vec![LanaExpr::int(2), LanaExpr::int(3)], None),
+            ])),
+            result
+        );
     }
 }
@@ -0,0 +1,284 @@
+use super::{eval, parser, LanaEnv, LanaErr, LanaExpr, Tokenizer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// The environment variable listing the directories to look for modules in, besides the program's
+/// own directory.
+const LANA_PATH: &str = "LANA_PATH";
+
+/// The namespace of the prelude. Its definitions can always be referred to as `lana.core/name`,
+/// even where `name` is shadowed.
+pub const CORE_NS: &str = "lana.core";
+
+/// The modules of an interpreter, shared by all of its namespaces.
+pub struct Modules {
+    /// The directories to look for modules in, after the program's own.
+    search_path: Vec<PathBuf>,
+    /// Modules are evaluated once, the first time they are required.
+    loaded: RefCell<HashMap<String, LanaEnv>>,
+    /// The modules being required, innermost last, to report circular requires.
+    requiring: RefCell<Vec<String>>,
+    /// The files being evaluated, innermost last.
+    files: RefCell<Vec<Rc<Path>>>,
+}
+
+/// A namespace, declared with `ns`. Its aliases are set by `require`.
+pub struct Namespace {
+    name: RefCell<String>,
+    /// Whether it was loaded by `require`, in which case `ns` must declare the required name.
+    required: bool,
+    aliases: RefCell<HashMap<String, String>>,
+    modules: Rc<Modules>,
+}
+
+impl Modules {
+    pub fn new(search_path: Vec<PathBuf>) -> Self {
+        Modules {
+            search_path,
+            loaded: RefCell::new(HashMap::new()),
+            requiring: RefCell::new(vec![]),
+            files: RefCell::new(vec![]),
+        }
+    }
+}
+
+/// Looks for modules in the directories listed in `LANA_PATH`.
+impl Default for Modules {
+    fn default() -> Self {
+        let search_path = std::env::var_os(LANA_PATH)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+
+        Modules::new(search_path)
+    }
+}
+
+impl Namespace {
+    pub fn new(name: &str, modules: Rc<Modules>) -> Self {
+        Namespace {
+            name: RefCell::new(name.to_string()),
+            required: false,
+            aliases: RefCell::new(HashMap::new()),
+            modules,
+        }
+    }
+
+    /// The loaded module `name` refers to, either by an alias or by its full name.
+    pub fn module(&self, name: &str) -> Option<LanaEnv> {
+        let aliases = self.aliases.borrow();
+        let name = aliases.get(name).map(String::as_str).unwrap_or(name);
+
+        self.modules.loaded.borrow().get(name).cloned()
+    }
+
+    /// The file being evaluated, if any.
+    pub fn current_file(&self) -> Option<Rc<Path>> {
+        self.modules.files.borrow().last().cloned()
+    }
+
+    /// Forgets the loaded modules, returning them.
+    pub fn unload_modules(&self) -> Vec<LanaEnv> {
+        let loaded = std::mem::take(&mut *self.modules.loaded.borrow_mut());
+
+        loaded.into_values().collect()
+    }
+}
+
+/// Evaluates every form in `src`, returning the value of the last one, or `nil` if there are none.
+pub fn eval_source(src: &str, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let tokens = Tokenizer::new(src).tokens();
+    let mut result = LanaExpr::Nil;
+
+    for (expr, span) in parser::parse_all_spanned(&tokens)? {
+        result = eval(&expr, env).map_err(|err| err.located(&span))?;
+    }
+
+    Ok(result)
+}
+
+/// Evaluates the file at `path`. While it is evaluated, `load` reads files relative to it. The
+/// spans of errors raised in it record that they are in it.
+pub fn eval_file(path: &Path, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|err| LanaErr::IoError(format!("Could not read '{}': {}", path.display(), err)))?;
+    let files = &env.ns().modules.files;
+    let file: Rc<Path> = Rc::from(path);
+
+    files.borrow_mut().push(file.clone());
+    let result = eval_source(&src, env);
+    files.borrow_mut().pop();
+
+    result.map_err(|err| err.in_file(&file))
+}
+
+/// Evaluates the file at `path`, relative to the file being evaluated, in `env`.
+pub fn load_file(path: &str, env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let path = match env.ns().modules.files.borrow().last() {
+        Some(file) => file.parent().unwrap_or_else(|| Path::new("")).join(path),
+        None => PathBuf::from(path),
+    };
+
+    eval_file(&path, env)
+}
+
+/// Requires the module described by `spec`, which is either its name or a vector of its name
+/// followed by options: `:as alias` to refer to it as `alias/name`, and `:refer [names]` to define
+/// some of its names in `env`.
+pub fn require(spec: &LanaExpr, env: &LanaEnv) -> Result<(), LanaErr> {
+    let (name, options) = match spec {
+        LanaExpr::Symbol(name) => (name, &[][..]),
+        LanaExpr::Vector(items) | LanaExpr::List(items, _) => match items.split_first() {
+            Some((LanaExpr::Symbol(name), options)) => (name, options),
+            _ => return Err(spec_error(spec)),
+        },
+        _ => return Err(spec_error(spec)),
+    };
+    let module = require_module(name, env)?;
+
+    for option in options.chunks(2) {
+        match option {
+            [LanaExpr::Keyword(key), LanaExpr::Symbol(alias)] if key == ":as" => {
+                env.ns()
+                    .aliases
+                    .borrow_mut()
+                    .insert(alias.clone(), name.clone());
+            }
+            [LanaExpr::Keyword(key), LanaExpr::Vector(names) | LanaExpr::List(names, _)]
+                if key == ":refer" =>
+            {
+                for referred in names {
+                    let referred = match referred {
+                        LanaExpr::Symbol(referred) => referred,
+                        other => {
+                            return Err(LanaErr::TypeError(format!(
+                                "Expected a symbol to refer, got {:?}",
+                                other
+                            )))
+                        }
+                    };
+                    let value = module.get_own(referred).ok_or_else(|| {
+                        LanaErr::Reason(format!("Module '{}' does not define '{}'", name, referred))
+                    })?;
+
+                    env.define(referred, value);
+                }
+            }
+            _ => {
+                return Err(LanaErr::Reason(format!(
+                    "Expected require options to be ':as alias' or ':refer [names]', got {:?}",
+                    option
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn spec_error(spec: &LanaExpr) -> LanaErr {
+    LanaErr::TypeError(format!(
+        "Expected a module name or a vector starting with one, got {:?}",
+        spec
+    ))
+}
+
+/// Evaluates the module `name` in a namespace of its own, unless it was already.
+fn require_module(name: &str, env: &LanaEnv) -> Result<LanaEnv, LanaErr> {
+    let modules = env.ns().modules.clone();
+
+    if let Some(module) = modules.loaded.borrow().get(name) {
+        return Ok(module.clone());
+    }
+
+    let requiring = modules.requiring.borrow().clone();
+    if let Some(start) = requiring.iter().position(|module| module == name) {
+        return Err(LanaErr::Reason(format!(
+            "Circular require: {} -> {}",
+            requiring[start..].join(" -> "),
+            name
+        )));
+    }
+
+    let path = find_module(name, &modules)?;
+    let module = env.root().with_namespace(Namespace {
+        required: true,
+        ..Namespace::new(name, modules.clone())
+    });
+
+    modules.requiring.borrow_mut().push(name.to_string());
+    let result = eval_file(&path, &module);
+    modules.requiring.borrow_mut().pop();
+    result?;
+
+    modules
+        .loaded
+        .borrow_mut()
+        .insert(name.to_string(), module.clone());
+
+    Ok(module)
+}
+
+/// Finds the file of the module `name`, so `my.utils` is read from `my/utils.lana`. Modules are
+/// looked for in the directory of the program's first file, then in the search path.
+fn find_module(name: &str, modules: &Modules) -> Result<PathBuf, LanaErr> {
+    let file = PathBuf::from(format!("{}.lana", name.replace('.', "/")));
+    let mut dirs: Vec<PathBuf> = vec![modules
+        .files
+        .borrow()
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default()];
+    dirs.extend(modules.search_path.iter().cloned());
+
+    dirs.iter()
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            LanaErr::IoError(format!(
+                "Could not find module '{}' in the program's directory or {}",
+                name, LANA_PATH
+            ))
+        })
+}
+
+/// `(ns name clauses)` names the namespace of the current file. Its clauses can be
+/// `(:require specs)`, which require modules like `require` does.
+pub fn eval_ns_args(args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+    let (name, clauses) = match args.split_first() {
+        Some((LanaExpr::Symbol(name), clauses)) => (name, clauses),
+        _ => return Err(LanaErr::Reason("Expected a namespace name".into())),
+    };
+
+    let ns = env.ns();
+    if ns.required && *ns.name.borrow() != *name {
+        return Err(LanaErr::Reason(format!(
+            "Module '{}' declares a different namespace, '{}'",
+            ns.name.borrow(),
+            name
+        )));
+    }
+    ns.name.replace(name.clone());
+
+    for clause in clauses {
+        match clause {
+            LanaExpr::List(items, _)
+                if items.first() == Some(&LanaExpr::Keyword(":require".into())) =>
+            {
+                for spec in &items[1..] {
+                    require(spec, env)?;
+                }
+            }
+            _ => {
+                return Err(LanaErr::Reason(format!(
+                    "Expected a (:require ...) clause, got {:?}",
+                    clause
+                )))
+            }
+        }
+    }
+
+    Ok(LanaExpr::Nil)
+}
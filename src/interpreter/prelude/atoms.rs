@@ -0,0 +1,141 @@
+use super::{apply, expect_args_count, LanaAtom, LanaEnv, LanaErr, LanaExpr};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "atom".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::Atom(Rc::new(LanaAtom::new(args[0].clone()))))
+        }),
+    );
+
+    prelude.insert(
+        "atom?".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::Bool(matches!(args[0], LanaExpr::Atom(_))))
+        }),
+    );
+
+    prelude.insert(
+        "deref".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let atom = parse_atom(&args[0])?;
+            let value = atom.value.borrow().clone();
+
+            Ok(value)
+        }),
+    );
+
+    prelude.insert(
+        "reset!".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 2)?;
+
+            let atom = parse_atom(&args[0])?;
+            set_value(atom, args[1].clone(), env)?;
+
+            Ok(args[1].clone())
+        }),
+    );
+
+    prelude.insert(
+        "swap!".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            if args.len() < 2 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected at least 2 arguments, got {}",
+                    args.len()
+                )));
+            }
+
+            let atom = parse_atom(&args[0])?;
+            let mut fn_args = vec![atom.value.borrow().clone()];
+            fn_args.extend_from_slice(&args[2..]);
+
+            let value = apply(&args[1], &fn_args, env)?;
+            set_value(atom, value.clone(), env)?;
+
+            Ok(value)
+        }),
+    );
+
+    prelude.insert(
+        "compare-and-set!".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 3)?;
+
+            let atom = parse_atom(&args[0])?;
+            if *atom.value.borrow() != args[1] {
+                return Ok(LanaExpr::Bool(false));
+            }
+            set_value(atom, args[2].clone(), env)?;
+
+            Ok(LanaExpr::Bool(true))
+        }),
+    );
+
+    prelude.insert(
+        "add-watch".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 3)?;
+
+            let atom = parse_atom(&args[0])?;
+            atom.watchers
+                .borrow_mut()
+                .insert(args[1].clone(), args[2].clone());
+
+            Ok(args[0].clone())
+        }),
+    );
+
+    prelude.insert(
+        "remove-watch".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let atom = parse_atom(&args[0])?;
+            atom.watchers.borrow_mut().shift_remove(&args[1]);
+
+            Ok(args[0].clone())
+        }),
+    );
+}
+
+fn parse_atom(expr: &LanaExpr) -> Result<&Rc<LanaAtom>, LanaErr> {
+    match expr {
+        LanaExpr::Atom(atom) => Ok(atom),
+        other => Err(LanaErr::TypeError(format!(
+            "Expected an atom, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// Sets the value of `atom`, then calls its watchers, in the order they were added.
+fn set_value(atom: &Rc<LanaAtom>, value: LanaExpr, env: &LanaEnv) -> Result<(), LanaErr> {
+    let old = atom.value.replace(value.clone());
+    // Watchers may add or remove watchers, so they can't be called while borrowed
+    let watchers = atom.watchers.borrow().clone();
+
+    for (key, watcher) in watchers {
+        apply(
+            &watcher,
+            &[
+                key,
+                LanaExpr::Atom(atom.clone()),
+                old.clone(),
+                value.clone(),
+            ],
+            env,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use super::{expect_args_count, expect_args_range, FromLana, LanaErr, LanaExpr};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::HashMap;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "get".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 2, 3)?;
+
+            let default = args.get(2).cloned().unwrap_or(LanaExpr::Nil);
+            let key = &args[1];
+
+            let value = match &args[0] {
+                LanaExpr::Map(map) => map.get(key).cloned(),
+                LanaExpr::Record(record) => record.get(key).cloned(),
+                LanaExpr::Set(set) => set.get(key).cloned(),
+                LanaExpr::List(xs, _) | LanaExpr::Vector(xs) => {
+                    as_index(key).and_then(|index| xs.get(index).cloned())
+                }
+                LanaExpr::Nil => None,
+                other => return Err(expected_collection(other)),
+            };
+
+            Ok(value.unwrap_or(default))
+        }),
+    );
+
+    prelude.insert(
+        "assoc".to_string(),
+        LanaExpr::Func(|args| {
+            let (coll, pairs) = args
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected a collection".into()))?;
+
+            if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+                return Err(LanaErr::ArityError(
+                    "Expected key and value pairs after the collection".into(),
+                ));
+            }
+
+            match coll {
+                LanaExpr::Map(_) | LanaExpr::Nil => {
+                    let mut map = match coll {
+                        LanaExpr::Map(map) => map.clone(),
+                        _ => IndexMap::new(),
+                    };
+
+                    for pair in pairs.chunks(2) {
+                        map.insert(pair[0].clone(), pair[1].clone());
+                    }
+
+                    Ok(LanaExpr::Map(map))
+                }
+                LanaExpr::Vector(vector) => {
+                    let mut vector = vector.clone();
+
+                    for pair in pairs.chunks(2) {
+                        match as_index(&pair[0]) {
+                            Some(index) if index < vector.len() => vector[index] = pair[1].clone(),
+                            Some(index) if index == vector.len() => vector.push(pair[1].clone()),
+                            _ => {
+                                return Err(LanaErr::IndexError(format!(
+                                    "Index {:?} out of bounds for vector of size {}",
+                                    pair[0],
+                                    vector.len()
+                                )))
+                            }
+                        }
+                    }
+
+                    Ok(LanaExpr::Vector(vector))
+                }
+                other => Err(LanaErr::TypeError(format!(
+                    "Expected a map or a vector, got {:?}",
+                    other
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "dissoc".to_string(),
+        LanaExpr::Func(|args| {
+            let (coll, keys) = args
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected a map".into()))?;
+
+            match coll {
+                LanaExpr::Map(map) => {
+                    let mut map = map.clone();
+
+                    for key in keys {
+                        map.shift_remove(key);
+                    }
+
+                    Ok(LanaExpr::Map(map))
+                }
+                LanaExpr::Nil => Ok(LanaExpr::Nil),
+                other => Err(LanaErr::TypeError(format!(
+                    "Expected a map, got {:?}",
+                    other
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "contains?".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let key = &args[1];
+
+            let contains = match &args[0] {
+                LanaExpr::Map(map) => map.contains_key(key),
+                LanaExpr::Set(set) => set.contains(key),
+                LanaExpr::List(xs, _) | LanaExpr::Vector(xs) => {
+                    as_index(key).is_some_and(|index| index < xs.len())
+                }
+                LanaExpr::Nil => false,
+                other => return Err(expected_collection(other)),
+            };
+
+            Ok(LanaExpr::Bool(contains))
+        }),
+    );
+
+    prelude.insert(
+        "keys".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            match &args[0] {
+                LanaExpr::Map(map) => Ok(LanaExpr::List(map.keys().cloned().collect(), None)),
+                LanaExpr::Nil => Ok(LanaExpr::Nil),
+                other => Err(LanaErr::TypeError(format!(
+                    "Expected a map, got {:?}",
+                    other
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "vals".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            match &args[0] {
+                LanaExpr::Map(map) => Ok(LanaExpr::List(map.values().cloned().collect(), None)),
+                LanaExpr::Nil => Ok(LanaExpr::Nil),
+                other => Err(LanaErr::TypeError(format!(
+                    "Expected a map, got {:?}",
+                    other
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "conj".to_string(),
+        LanaExpr::Func(|args| {
+            let (coll, items) = args
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected a collection".into()))?;
+
+            match coll {
+                // Lists grow at the front, like in Clojure
+                LanaExpr::List(list, _) => Ok(LanaExpr::List(
+                    items.iter().rev().chain(list.iter()).cloned().collect(),
+                    None,
+                )),
+                LanaExpr::Nil => Ok(LanaExpr::List(items.iter().rev().cloned().collect(), None)),
+                LanaExpr::Vector(vector) => Ok(LanaExpr::Vector(
+                    vector.iter().chain(items.iter()).cloned().collect(),
+                )),
+                LanaExpr::Set(set) => {
+                    let mut set: IndexSet<LanaExpr> = set.clone();
+                    set.extend(items.iter().cloned());
+
+                    Ok(LanaExpr::Set(set))
+                }
+                LanaExpr::Map(map) => {
+                    let mut map = map.clone();
+
+                    for item in items {
+                        match item {
+                            LanaExpr::Vector(pair) if pair.len() == 2 => {
+                                map.insert(pair[0].clone(), pair[1].clone());
+                            }
+                            LanaExpr::Map(other) => map.extend(other.clone()),
+                            other => {
+                                return Err(LanaErr::TypeError(format!(
+                                    "Expected a [key value] vector or a map, got {:?}",
+                                    other
+                                )))
+                            }
+                        }
+                    }
+
+                    Ok(LanaExpr::Map(map))
+                }
+                other => Err(expected_collection(other)),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "count".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let count = match &args[0] {
+                LanaExpr::List(xs, _) | LanaExpr::Vector(xs) => xs.len(),
+                LanaExpr::Map(map) => map.len(),
+                LanaExpr::Set(set) => set.len(),
+                LanaExpr::String(s) => s.chars().count(),
+                LanaExpr::Nil => 0,
+                other => return Err(expected_collection(other)),
+            };
+
+            Ok(LanaExpr::int(count as i64))
+        }),
+    );
+}
+
+fn as_index(expr: &LanaExpr) -> Option<usize> {
+    usize::from_lana(expr).ok()
+}
+
+fn expected_collection(expr: &LanaExpr) -> LanaErr {
+    LanaErr::TypeError(format!("Expected a collection, got {:?}", expr))
+}
@@ -0,0 +1,84 @@
+use super::{expect_args_count, expect_args_range, LanaErr, LanaError, LanaExpr};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "ex-info".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 1, 2)?;
+
+            let message = match &args[0] {
+                LanaExpr::String(message) => message.clone(),
+                other => {
+                    return Err(LanaErr::TypeError(format!(
+                        "Expected error message to be a string, got {:?}",
+                        other
+                    )))
+                }
+            };
+
+            Ok(LanaExpr::Error(Rc::new(LanaError {
+                kind: ":ex-info".into(),
+                message,
+                data: args.get(1).cloned().unwrap_or(LanaExpr::Nil),
+                span: None,
+            })))
+        }),
+    );
+
+    prelude.insert(
+        "throw".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Err(LanaErr::Thrown(parse_error(&args[0])?.clone()))
+        }),
+    );
+
+    prelude.insert(
+        "ex-message".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::String(parse_error(&args[0])?.message.clone()))
+        }),
+    );
+
+    prelude.insert(
+        "ex-data".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(parse_error(&args[0])?.data.clone())
+        }),
+    );
+
+    prelude.insert(
+        "ex-kind".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::Keyword(parse_error(&args[0])?.kind.clone()))
+        }),
+    );
+
+    prelude.insert(
+        "error?".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::Bool(matches!(args[0], LanaExpr::Error(_))))
+        }),
+    );
+}
+
+fn parse_error(expr: &LanaExpr) -> Result<&Rc<LanaError>, LanaErr> {
+    match expr {
+        LanaExpr::Error(error) => Ok(error),
+        other => Err(LanaErr::TypeError(format!(
+            "Expected an error, got {:?}",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,228 @@
+use super::strings::to_str;
+use super::{expect_args_count, FromLana, LanaErr, LanaExpr};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "slurp".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let path = parse_path(&args[0])?;
+            let content =
+                fs::read_to_string(&path).map_err(|err| io_error("Could not read", &path, err))?;
+
+            Ok(LanaExpr::String(content))
+        }),
+    );
+
+    prelude.insert(
+        "spit".to_string(),
+        LanaExpr::Func(|args| {
+            let append = match args {
+                [_, _] => false,
+                [_, _, LanaExpr::Keyword(option), append] if option == ":append" => {
+                    append.is_truthy()
+                }
+                [_, _, ..] => {
+                    return Err(LanaErr::Reason(
+                        "Expected spit options to be ':append true' or ':append false'".into(),
+                    ))
+                }
+                _ => {
+                    return Err(LanaErr::ArityError(format!(
+                        "Expected 2 or 4 arguments, got {}",
+                        args.len()
+                    )))
+                }
+            };
+
+            let path = parse_path(&args[0])?;
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(append)
+                .truncate(!append)
+                .open(&path)
+                .map_err(|err| io_error("Could not open", &path, err))?;
+            file.write_all(to_str(&args[1]).as_bytes())
+                .map_err(|err| io_error("Could not write", &path, err))?;
+
+            Ok(LanaExpr::Nil)
+        }),
+    );
+
+    prelude.insert(
+        "read-lines".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let path = parse_path(&args[0])?;
+            let content =
+                fs::read_to_string(&path).map_err(|err| io_error("Could not read", &path, err))?;
+            let lines = content
+                .lines()
+                .map(|line| LanaExpr::String(line.into()))
+                .collect();
+
+            Ok(LanaExpr::List(lines, None))
+        }),
+    );
+
+    prelude.insert(
+        "file-exists?".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::Bool(parse_path(&args[0])?.exists()))
+        }),
+    );
+
+    prelude.insert(
+        "list-dir".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let path = parse_path(&args[0])?;
+            let mut entries = fs::read_dir(&path)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(|err| io_error("Could not list", &path, err))?;
+            // The order of `read_dir` depends on the platform
+            entries.sort();
+
+            Ok(LanaExpr::List(
+                entries
+                    .iter()
+                    .map(PathBuf::as_path)
+                    .map(path_value)
+                    .collect(),
+                None,
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "mkdir".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let path = parse_path(&args[0])?;
+            fs::create_dir_all(&path).map_err(|err| io_error("Could not create", &path, err))?;
+
+            Ok(LanaExpr::Nil)
+        }),
+    );
+
+    prelude.insert(
+        "delete-file".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            // Directories are only deleted when empty
+            let path = parse_path(&args[0])?;
+            let result = if path.is_dir() {
+                fs::remove_dir(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            result.map_err(|err| io_error("Could not delete", &path, err))?;
+
+            Ok(LanaExpr::Nil)
+        }),
+    );
+
+    prelude.insert(
+        "copy-file".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let from = parse_path(&args[0])?;
+            let to = parse_path(&args[1])?;
+            fs::copy(&from, &to).map_err(|err| {
+                LanaErr::IoError(format!(
+                    "Could not copy '{}' to '{}': {}",
+                    from.display(),
+                    to.display(),
+                    err
+                ))
+            })?;
+
+            Ok(LanaExpr::Nil)
+        }),
+    );
+
+    prelude.insert(
+        "path/join".to_string(),
+        LanaExpr::Func(|args| {
+            if args.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one path".into()));
+            }
+
+            let mut path = PathBuf::new();
+            for part in args {
+                path.push(parse_path(part)?);
+            }
+
+            Ok(path_value(&path))
+        }),
+    );
+
+    prelude.insert(
+        "path/file-name".to_string(),
+        LanaExpr::Func(|args| path_part(args, |path| path.file_name())),
+    );
+
+    prelude.insert(
+        "path/parent".to_string(),
+        LanaExpr::Func(|args| path_part(args, |path| path.parent().map(Path::as_os_str))),
+    );
+
+    prelude.insert(
+        "path/extension".to_string(),
+        LanaExpr::Func(|args| path_part(args, |path| path.extension())),
+    );
+
+    prelude.insert(
+        "path/absolute".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let path = parse_path(&args[0])?;
+            let absolute =
+                fs::canonicalize(&path).map_err(|err| io_error("Could not resolve", &path, err))?;
+
+            Ok(path_value(&absolute))
+        }),
+    );
+}
+
+fn parse_path(expr: &LanaExpr) -> Result<PathBuf, LanaErr> {
+    String::from_lana(expr).map(PathBuf::from)
+}
+
+fn path_value(path: &Path) -> LanaExpr {
+    LanaExpr::String(path.to_string_lossy().into_owned())
+}
+
+/// Returns a part of a path, or `nil` if it doesn't have one.
+fn path_part(
+    args: &[LanaExpr],
+    part: impl Fn(&Path) -> Option<&std::ffi::OsStr>,
+) -> Result<LanaExpr, LanaErr> {
+    expect_args_count(args, 1)?;
+
+    let path = parse_path(&args[0])?;
+
+    Ok(part(&path).map_or(LanaExpr::Nil, |part| path_value(Path::new(part))))
+}
+
+fn io_error(action: &str, path: &Path, err: std::io::Error) -> LanaErr {
+    LanaErr::IoError(format!("{} '{}': {}", action, path.display(), err))
+}
@@ -0,0 +1,31 @@
+use super::{expect_args_count, LanaErr, LanaExpr};
+use std::collections::HashMap;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "json/parse".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            match &args[0] {
+                LanaExpr::String(json) => serde_json::from_str(json)
+                    .map_err(|err| LanaErr::ParseError(format!("Invalid JSON: {}", err))),
+                other => Err(LanaErr::TypeError(format!(
+                    "Expected a string, got {:?}",
+                    other
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "json/stringify".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            serde_json::to_string(&args[0])
+                .map(LanaExpr::String)
+                .map_err(|err| LanaErr::TypeError(err.to_string()))
+        }),
+    );
+}
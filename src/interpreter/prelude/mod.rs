@@ -0,0 +1,434 @@
+mod atoms;
+mod collections;
+mod errors;
+mod files;
+#[cfg(feature = "serde")]
+mod json;
+mod namespaces;
+mod regexes;
+mod sequences;
+mod strings;
+
+use super::{
+    apply, eval, modules, parser, FromLana, LanaAtom, LanaEnv, LanaErr, LanaError, LanaExpr,
+    LanaNumber, LanaRegex, NativeFn, Tokenizer,
+};
+use indexmap::{IndexMap, IndexSet};
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicUsize};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// The part of the prelude written in Lana, evaluated after the builtins by `load_core`.
+const CORE: &str = include_str!("core.lana");
+
+static GENSYM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Checks that each value compares to the next one as `$ordering`. Numbers are compared by value,
+/// regardless of their types.
+macro_rules! ensure_tonicity {
+    ($($ordering:pat)|+) => {{
+        |args: &[LanaExpr]| -> Result<LanaExpr, LanaErr> {
+            if args.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one argument".to_string()));
+            }
+
+            for pair in args.windows(2) {
+                let ordering = match (&pair[0], &pair[1]) {
+                    // Unlike `compare`, nothing is ordered with NaN
+                    (LanaExpr::Number(a), LanaExpr::Number(b)) => a.compare(b),
+                    (a, b) => Some(compare(a, b)?),
+                };
+
+                if !matches!(ordering, Some($($ordering)|+)) {
+                    return Ok(LanaExpr::Bool(false));
+                }
+            }
+
+            Ok(LanaExpr::Bool(true))
+        }
+    }};
+}
+
+pub fn prelude() -> HashMap<String, LanaExpr> {
+    let mut prelude: HashMap<String, LanaExpr> = HashMap::new();
+
+    prelude.insert(
+        "+".to_string(),
+        LanaExpr::Func(|args| {
+            let sum = parse_list_of_numbers(args)?
+                .iter()
+                .fold(LanaNumber::Int(0), |sum, n| sum.add(n));
+
+            Ok(LanaExpr::Number(sum))
+        }),
+    );
+
+    prelude.insert(
+        "-".to_string(),
+        LanaExpr::Func(|args| {
+            let numbers = parse_list_of_numbers(args)?;
+            let (first, rest) = numbers
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected at least one number".into()))?;
+            let difference = rest.iter().fold(first.clone(), |res, n| res.sub(n));
+
+            Ok(LanaExpr::Number(difference))
+        }),
+    );
+
+    prelude.insert(
+        "*".to_string(),
+        LanaExpr::Func(|args| {
+            let result = parse_list_of_numbers(args)?
+                .iter()
+                .fold(LanaNumber::Int(1), |res, n| res.mul(n));
+
+            Ok(LanaExpr::Number(result))
+        }),
+    );
+
+    prelude.insert(
+        "/".to_string(),
+        LanaExpr::Func(|args| {
+            let numbers = parse_list_of_numbers(args)?;
+            let (first, rest) = numbers
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected at least one number".into()))?;
+            let quotient = rest.iter().try_fold(first.clone(), |res, n| res.div(n))?;
+
+            Ok(LanaExpr::Number(quotient))
+        }),
+    );
+
+    prelude.insert(
+        "=".to_string(),
+        LanaExpr::Func(|args| {
+            if args.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one argument".into()));
+            }
+
+            Ok(LanaExpr::Bool(
+                args.windows(2).all(|pair| pair[0] == pair[1]),
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "not=".to_string(),
+        LanaExpr::Func(|args| {
+            if args.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one argument".into()));
+            }
+
+            Ok(LanaExpr::Bool(
+                !args.windows(2).all(|pair| pair[0] == pair[1]),
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "==".to_string(),
+        LanaExpr::Func(|args| {
+            if args.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one argument".into()));
+            }
+            let numbers = parse_list_of_numbers(args)?;
+
+            Ok(LanaExpr::Bool(numbers.windows(2).all(|pair| {
+                pair[0].compare(&pair[1]) == Some(Ordering::Equal)
+            })))
+        }),
+    );
+
+    prelude.insert(
+        "compare".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let ordering = match compare(&args[0], &args[1])? {
+                Ordering::Less => -1,
+                Ordering::Equal => 0,
+                Ordering::Greater => 1,
+            };
+
+            Ok(LanaExpr::int(ordering))
+        }),
+    );
+
+    prelude.insert(
+        ">".to_string(),
+        LanaExpr::Func(ensure_tonicity!(Ordering::Greater)),
+    );
+    prelude.insert(
+        ">=".to_string(),
+        LanaExpr::Func(ensure_tonicity!(Ordering::Greater | Ordering::Equal)),
+    );
+    prelude.insert(
+        "<".to_string(),
+        LanaExpr::Func(ensure_tonicity!(Ordering::Less)),
+    );
+    prelude.insert(
+        "<=".to_string(),
+        LanaExpr::Func(ensure_tonicity!(Ordering::Less | Ordering::Equal)),
+    );
+
+    prelude.insert(
+        "<=".to_string(),
+        LanaExpr::Func(ensure_tonicity!(Ordering::Less | Ordering::Equal)),
+    );
+
+    prelude.insert(
+        "nil?".to_string(),
+        LanaExpr::Func(|args| {
+            if args.len() != 1 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected one argument, got {}",
+                    args.len()
+                )));
+            }
+
+            match args[0] {
+                LanaExpr::Nil => Ok(LanaExpr::Bool(true)),
+                _ => Ok(LanaExpr::Bool(false)),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "some?".to_string(),
+        LanaExpr::Func(|args| {
+            if args.len() != 1 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected one argument, got {}",
+                    args.len()
+                )));
+            }
+
+            match args[0] {
+                LanaExpr::Nil => Ok(LanaExpr::Bool(false)),
+                _ => Ok(LanaExpr::Bool(true)),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "println".to_string(),
+        LanaExpr::Func(|args| {
+            if args.is_empty() {
+                println!();
+
+                Ok(LanaExpr::Nil)
+            } else {
+                for arg in args {
+                    println!("{}", arg);
+                }
+
+                Ok(args[0].clone())
+            }
+        }),
+    );
+
+    prelude.insert(
+        "print".to_string(),
+        LanaExpr::Func(|args| {
+            use std::io::{self, Write};
+
+            if args.is_empty() {
+                Ok(LanaExpr::Nil)
+            } else {
+                for arg in args {
+                    print!("{}", arg);
+                }
+                io::stdout().flush().expect("Could not flush stdout");
+                Ok(args[0].clone())
+            }
+        }),
+    );
+
+    prelude.insert(
+        "gets".to_string(),
+        LanaExpr::Func(|args| {
+            if !args.is_empty() {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected no arguments, got {}",
+                    args.len()
+                )));
+            }
+
+            let mut s = String::new();
+
+            std::io::stdin()
+                .read_line(&mut s)
+                .map(|_| s = s.trim().to_string())
+                .map_err(|e| LanaErr::IoError(format!("Failed to read line: {}", e)))?;
+
+            Ok(LanaExpr::String(s))
+        }),
+    );
+
+    prelude.insert(
+        "num".to_string(),
+        LanaExpr::Func(|args| {
+            if args.len() != 1 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected 1 argument, got {}",
+                    args.len()
+                )));
+            }
+
+            let token = args[0].clone();
+            match &token {
+                LanaExpr::String(s) => match LanaNumber::parse(s) {
+                    Some(n) => Ok(LanaExpr::Number(n)),
+                    None => Err(LanaErr::ParseError(format!(
+                        "Could not parse {:?} into a number",
+                        token
+                    ))),
+                },
+                LanaExpr::Number(_) => Ok(token.clone()),
+                _ => Err(LanaErr::TypeError(format!(
+                    "expected argument to be a string, got {:?}",
+                    token
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "sleep".to_string(),
+        LanaExpr::Func(|args| {
+            if args.len() != 1 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected 1 argument, got {}",
+                    args.len()
+                )));
+            }
+
+            match &args[0] {
+                LanaExpr::Number(time) => {
+                    let seconds = time.to_f64() as u64;
+                    sleep(Duration::new(seconds, 0));
+
+                    Ok(LanaExpr::Number(BigInt::from(seconds).into()))
+                }
+                expr => Err(LanaErr::TypeError(format!(
+                    "Invalid argument: expected number, got {:?}",
+                    expr
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "type".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::String(args[0].enum_name()))
+        }),
+    );
+
+    prelude.insert(
+        "gensym".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 0, 1)?;
+
+            let prefix = match args.first() {
+                Some(prefix) => String::from_lana(prefix)?,
+                None => "G".into(),
+            };
+            let id = GENSYM_COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+
+            Ok(LanaExpr::Symbol(format!("{}__{}", prefix, id)))
+        }),
+    );
+
+    atoms::load(&mut prelude);
+    collections::load(&mut prelude);
+    errors::load(&mut prelude);
+    files::load(&mut prelude);
+    #[cfg(feature = "serde")]
+    json::load(&mut prelude);
+    namespaces::load(&mut prelude);
+    regexes::load(&mut prelude);
+    sequences::load(&mut prelude);
+    strings::load(&mut prelude);
+
+    // Builtins are registered as native functions, so they know their own name
+    prelude
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                LanaExpr::Func(function) => LanaExpr::Native(NativeFn::new(&name, function)),
+                LanaExpr::EnvFunc(function) => {
+                    LanaExpr::Native(NativeFn::with_env(&name, function))
+                }
+                value => value,
+            };
+
+            (name, value)
+        })
+        .collect()
+}
+
+/// Evaluates `core.lana` in `env`. Its forms are evaluated without their spans, so errors raised in
+/// its functions point at where they were called from instead.
+pub fn load_core(env: &LanaEnv) {
+    let tokens = Tokenizer::new(CORE).tokens();
+
+    for (form, _) in parser::parse_all_spanned(&tokens).expect("core.lana should parse") {
+        eval(&without_spans(&form), env).expect("core.lana should evaluate");
+    }
+}
+
+fn without_spans(expr: &LanaExpr) -> LanaExpr {
+    match expr {
+        LanaExpr::List(items, _) => LanaExpr::List(items.iter().map(without_spans).collect(), None),
+        LanaExpr::Vector(items) => LanaExpr::Vector(items.iter().map(without_spans).collect()),
+        LanaExpr::Map(map) => LanaExpr::Map(
+            map.iter()
+                .map(|(key, value)| (without_spans(key), without_spans(value)))
+                .collect::<IndexMap<_, _>>(),
+        ),
+        LanaExpr::Set(set) => LanaExpr::Set(set.iter().map(without_spans).collect::<IndexSet<_>>()),
+        other => other.clone(),
+    }
+}
+
+fn expect_args_count(args: &[LanaExpr], count: usize) -> Result<(), LanaErr> {
+    if args.len() != count {
+        return Err(LanaErr::ArityError(format!(
+            "Expected {} argument(s), got {}",
+            count,
+            args.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn expect_args_range(args: &[LanaExpr], min: usize, max: usize) -> Result<(), LanaErr> {
+    if args.len() < min || args.len() > max {
+        return Err(LanaErr::ArityError(format!(
+            "Expected {}-{} arguments, got {}",
+            min,
+            max,
+            args.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Orders values with `LanaExpr::compare`, failing when they can't be ordered.
+fn compare(a: &LanaExpr, b: &LanaExpr) -> Result<Ordering, LanaErr> {
+    a.compare(b)
+        .ok_or_else(|| LanaErr::TypeError(format!("Cannot compare {:?} with {:?}", a, b)))
+}
+
+fn parse_list_of_numbers(list: &[LanaExpr]) -> Result<Vec<LanaNumber>, LanaErr> {
+    list.iter().map(LanaNumber::from_lana).collect()
+}
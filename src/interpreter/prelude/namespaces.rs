@@ -0,0 +1,25 @@
+use super::modules::{load_file, require};
+use super::{expect_args_count, FromLana, LanaExpr};
+use std::collections::HashMap;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "load".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 1)?;
+
+            load_file(&String::from_lana(&args[0])?, env)
+        }),
+    );
+
+    prelude.insert(
+        "require".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            for spec in args {
+                require(spec, env)?;
+            }
+
+            Ok(LanaExpr::Nil)
+        }),
+    );
+}
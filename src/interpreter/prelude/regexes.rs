@@ -0,0 +1,158 @@
+use super::{apply, expect_args_count, FromLana, LanaErr, LanaExpr, LanaRegex};
+use indexmap::IndexMap;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "re-pattern".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let pattern = String::from_lana(&args[0])?;
+
+            LanaRegex::new(&pattern)
+                .map(|regex| LanaExpr::Regex(Rc::new(regex)))
+                .map_err(|err| LanaErr::Reason(format!("Invalid regex: {}", err)))
+        }),
+    );
+
+    prelude.insert(
+        "re-find".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let (regex, s) = parse_regex_and_string(args)?;
+            let regex = regex.regex();
+
+            Ok(regex
+                .captures(&s)
+                .map_or(LanaExpr::Nil, |captures| match_value(regex, &captures)))
+        }),
+    );
+
+    prelude.insert(
+        "re-matches".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let (regex, s) = parse_regex_and_string(args)?;
+
+            Ok(regex
+                .anchored()
+                .captures(&s)
+                .map_or(LanaExpr::Nil, |captures| {
+                    match_value(regex.regex(), &captures)
+                }))
+        }),
+    );
+
+    prelude.insert(
+        "re-seq".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let (regex, s) = parse_regex_and_string(args)?;
+            let regex = regex.regex();
+            let matches = regex
+                .captures_iter(&s)
+                .map(|captures| match_value(regex, &captures))
+                .collect();
+
+            Ok(LanaExpr::List(matches, None))
+        }),
+    );
+
+    prelude.insert(
+        "re-replace".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 3)?;
+
+            let (regex, s) = parse_regex_and_string(args)?;
+            let regex = regex.regex();
+
+            match &args[2] {
+                // `$1` and `${name}` in the replacement refer to groups
+                LanaExpr::String(replacement) => Ok(LanaExpr::String(
+                    regex.replace_all(&s, replacement.as_str()).into_owned(),
+                )),
+                function => {
+                    let mut result = String::new();
+                    let mut last_end = 0;
+
+                    for captures in regex.captures_iter(&s) {
+                        let whole = captures.get(0).expect("the whole match is always a group");
+                        let replacement = apply(function, &[match_value(regex, &captures)], env)?;
+
+                        result.push_str(&s[last_end..whole.start()]);
+                        result.push_str(&String::from_lana(&replacement)?);
+                        last_end = whole.end();
+                    }
+                    result.push_str(&s[last_end..]);
+
+                    Ok(LanaExpr::String(result))
+                }
+            }
+        }),
+    );
+
+    prelude.insert(
+        "re-split".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let (regex, s) = parse_regex_and_string(args)?;
+            let regex = regex.regex();
+            let parts = regex
+                .split(&s)
+                .map(|part| LanaExpr::String(part.into()))
+                .collect();
+
+            Ok(LanaExpr::Vector(parts))
+        }),
+    );
+}
+
+fn parse_regex_and_string(args: &[LanaExpr]) -> Result<(&LanaRegex, String), LanaErr> {
+    let regex = match &args[0] {
+        LanaExpr::Regex(regex) => regex,
+        other => {
+            return Err(LanaErr::TypeError(format!(
+                "Expected a regex, got {:?}",
+                other
+            )))
+        }
+    };
+
+    Ok((regex, String::from_lana(&args[1])?))
+}
+
+/// What a match evaluates to: the matched string when the regex has no groups, a map from group
+/// names to what they matched when its groups are named, or else a vector with the whole match
+/// followed by each group. Groups that didn't match are `nil`.
+fn match_value(regex: &Regex, captures: &Captures) -> LanaExpr {
+    let group =
+        |m: Option<regex::Match>| m.map_or(LanaExpr::Nil, |m| LanaExpr::String(m.as_str().into()));
+
+    if regex.captures_len() == 1 {
+        return group(captures.get(0));
+    }
+
+    let names: Vec<&str> = regex.capture_names().flatten().collect();
+    if !names.is_empty() {
+        let map: IndexMap<LanaExpr, LanaExpr> = names
+            .into_iter()
+            .map(|name| {
+                (
+                    LanaExpr::Keyword(format!(":{}", name)),
+                    group(captures.name(name)),
+                )
+            })
+            .collect();
+
+        return LanaExpr::Map(map);
+    }
+
+    LanaExpr::Vector(captures.iter().map(group).collect())
+}
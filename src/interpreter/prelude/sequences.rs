@@ -0,0 +1,310 @@
+use super::{
+    apply, compare, expect_args_count, expect_args_range, FromLana, LanaErr, LanaExpr, LanaNumber,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "list".to_string(),
+        LanaExpr::Func(|args| Ok(LanaExpr::List(args.to_vec(), None))),
+    );
+
+    prelude.insert(
+        "cons".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let mut list = vec![args[0].clone()];
+            list.extend(seq_items(&args[1])?);
+
+            Ok(LanaExpr::List(list, None))
+        }),
+    );
+
+    prelude.insert(
+        "first".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(seq_items(&args[0])?
+                .into_iter()
+                .next()
+                .unwrap_or(LanaExpr::Nil))
+        }),
+    );
+
+    prelude.insert(
+        "rest".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::List(
+                seq_items(&args[0])?.into_iter().skip(1).collect(),
+                None,
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "nth".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 2, 3)?;
+
+            let items = seq_items(&args[0])?;
+            let index = parse_count(&args[1])?;
+
+            match (items.get(index), args.get(2)) {
+                (Some(item), _) => Ok(item.clone()),
+                (None, Some(default)) => Ok(default.clone()),
+                (None, None) => Err(LanaErr::IndexError(format!(
+                    "Index {} out of bounds for sequence of size {}",
+                    index,
+                    items.len()
+                ))),
+            }
+        }),
+    );
+
+    prelude.insert(
+        "concat".to_string(),
+        LanaExpr::Func(|args| {
+            let mut list = vec![];
+
+            for arg in args {
+                list.extend(seq_items(arg)?);
+            }
+
+            Ok(LanaExpr::List(list, None))
+        }),
+    );
+
+    prelude.insert(
+        "map".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            let (function, colls) = args
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected a function".into()))?;
+
+            if colls.is_empty() {
+                return Err(LanaErr::ArityError("Expected at least one sequence".into()));
+            }
+
+            let colls = colls
+                .iter()
+                .map(seq_items)
+                .collect::<Result<Vec<Vec<LanaExpr>>, LanaErr>>()?;
+            let shortest = colls.iter().map(|coll| coll.len()).min().unwrap_or(0);
+
+            let mut list = vec![];
+            for index in 0..shortest {
+                let fn_args: Vec<LanaExpr> = colls.iter().map(|coll| coll[index].clone()).collect();
+
+                list.push(apply(function, &fn_args, env)?);
+            }
+
+            Ok(LanaExpr::List(list, None))
+        }),
+    );
+
+    prelude.insert(
+        "filter".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 2)?;
+
+            let mut list = vec![];
+            for item in seq_items(&args[1])? {
+                if apply(&args[0], std::slice::from_ref(&item), env)?.is_truthy() {
+                    list.push(item);
+                }
+            }
+
+            Ok(LanaExpr::List(list, None))
+        }),
+    );
+
+    prelude.insert(
+        "reduce".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_range(args, 2, 3)?;
+
+            let function = &args[0];
+            let (init, mut items) = match args {
+                [_, coll] => {
+                    let mut items = seq_items(coll)?.into_iter();
+
+                    match items.next() {
+                        Some(first) => (first, items),
+                        None => return apply(function, &[], env),
+                    }
+                }
+                [_, init, coll] => (init.clone(), seq_items(coll)?.into_iter()),
+                _ => unreachable!("reduce takes 2-3 arguments"),
+            };
+
+            items.try_fold(init, |acc, item| apply(function, &[acc, item], env))
+        }),
+    );
+
+    prelude.insert(
+        "apply".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            if args.len() < 2 {
+                return Err(LanaErr::ArityError(format!(
+                    "Expected at least 2 arguments, got {}",
+                    args.len()
+                )));
+            }
+
+            let (function, fn_args) = args.split_first().expect("Already checked length");
+            let (coll, fn_args) = fn_args.split_last().expect("Already checked length");
+
+            let mut fn_args = fn_args.to_vec();
+            fn_args.extend(seq_items(coll)?);
+
+            apply(function, &fn_args, env)
+        }),
+    );
+
+    prelude.insert(
+        "range".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 1, 3)?;
+
+            let numbers = super::parse_list_of_numbers(args)?;
+            let (start, end, step) = match numbers.as_slice() {
+                [end] => (LanaNumber::Int(0), end, LanaNumber::Int(1)),
+                [start, end] => (start.clone(), end, LanaNumber::Int(1)),
+                [start, end, step] => (start.clone(), end, step.clone()),
+                _ => unreachable!("range takes 1-3 arguments"),
+            };
+
+            if step.is_zero() {
+                return Err(LanaErr::Reason("Expected range step to not be 0".into()));
+            }
+
+            let direction = step.compare(&LanaNumber::Int(0));
+            let mut list = vec![];
+            let mut current = start;
+            while current.compare(end) == direction.map(Ordering::reverse) {
+                let next = current.add(&step);
+                list.push(LanaExpr::Number(current));
+                current = next;
+            }
+
+            Ok(LanaExpr::List(list, None))
+        }),
+    );
+
+    prelude.insert(
+        "take".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let count = parse_count(&args[0])?;
+
+            Ok(LanaExpr::List(
+                seq_items(&args[1])?.into_iter().take(count).collect(),
+                None,
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "drop".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let count = parse_count(&args[0])?;
+
+            Ok(LanaExpr::List(
+                seq_items(&args[1])?.into_iter().skip(count).collect(),
+                None,
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "reverse".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            Ok(LanaExpr::List(
+                seq_items(&args[0])?.into_iter().rev().collect(),
+                None,
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "sort".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let keyed = seq_items(&args[0])?
+                .into_iter()
+                .map(|item| (item.clone(), item))
+                .collect();
+
+            sort_by_keys(keyed)
+        }),
+    );
+
+    prelude.insert(
+        "sort-by".to_string(),
+        LanaExpr::EnvFunc(|args, env| {
+            expect_args_count(args, 2)?;
+
+            let keyed = seq_items(&args[1])?
+                .into_iter()
+                .map(|item| Ok((apply(&args[0], std::slice::from_ref(&item), env)?, item)))
+                .collect::<Result<Vec<(LanaExpr, LanaExpr)>, LanaErr>>()?;
+
+            sort_by_keys(keyed)
+        }),
+    );
+}
+
+/// Returns the elements of anything that can be seen as a sequence. Maps are seen as a sequence of
+/// `[key value]` vectors and strings as a sequence of single character strings.
+pub fn seq_items(expr: &LanaExpr) -> Result<Vec<LanaExpr>, LanaErr> {
+    match expr {
+        LanaExpr::List(xs, _) | LanaExpr::Vector(xs) => Ok(xs.clone()),
+        LanaExpr::Set(set) => Ok(set.iter().cloned().collect()),
+        LanaExpr::Map(map) => Ok(map
+            .iter()
+            .map(|(key, value)| LanaExpr::Vector(vec![key.clone(), value.clone()]))
+            .collect()),
+        LanaExpr::String(s) => Ok(s.chars().map(|c| LanaExpr::String(c.to_string())).collect()),
+        LanaExpr::Nil => Ok(vec![]),
+        other => Err(LanaErr::TypeError(format!(
+            "Expected a sequence, got {:?}",
+            other
+        ))),
+    }
+}
+
+fn parse_count(expr: &LanaExpr) -> Result<usize, LanaErr> {
+    usize::from_lana(expr)
+        .map_err(|_| LanaErr::TypeError(format!("Expected a non-negative integer, got {:?}", expr)))
+}
+
+/// Stable sorts `(key, item)` pairs by key, returning the items.
+fn sort_by_keys(mut keyed: Vec<(LanaExpr, LanaExpr)>) -> Result<LanaExpr, LanaErr> {
+    let mut error = None;
+
+    keyed.sort_by(|(a, _), (b, _)| {
+        compare(a, b).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(LanaExpr::List(
+            keyed.into_iter().map(|(_, item)| item).collect(),
+            None,
+        )),
+    }
+}
@@ -0,0 +1,336 @@
+use super::sequences::seq_items;
+use super::{expect_args_count, expect_args_range, FromLana, LanaErr, LanaExpr};
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub fn load(prelude: &mut HashMap<String, LanaExpr>) {
+    prelude.insert(
+        "str".to_string(),
+        LanaExpr::Func(|args| Ok(LanaExpr::String(args.iter().map(to_str).collect()))),
+    );
+
+    prelude.insert(
+        "subs".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 2, 3)?;
+
+            let s = String::from_lana(&args[0])?;
+            let len = s.chars().count();
+            let start = usize::from_lana(&args[1])?;
+            let end = match args.get(2) {
+                Some(end) => usize::from_lana(end)?,
+                None => len,
+            };
+
+            if start > end || end > len {
+                return Err(LanaErr::IndexError(format!(
+                    "Substring {}..{} is out of bounds for a string of length {}",
+                    start, end, len
+                )));
+            }
+
+            Ok(LanaExpr::String(
+                s.chars().skip(start).take(end - start).collect(),
+            ))
+        }),
+    );
+
+    prelude.insert(
+        "format".to_string(),
+        LanaExpr::Func(|args| {
+            let (template, args) = args
+                .split_first()
+                .ok_or_else(|| LanaErr::ArityError("Expected a format string".into()))?;
+
+            format(&String::from_lana(template)?, args).map(LanaExpr::String)
+        }),
+    );
+
+    prelude.insert(
+        "str/split".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let s = String::from_lana(&args[0])?;
+            let separator = String::from_lana(&args[1])?;
+            let parts = if separator.is_empty() {
+                s.chars().map(String::from).collect()
+            } else {
+                s.split(separator.as_str()).map(String::from).collect()
+            };
+
+            Ok(strings(parts))
+        }),
+    );
+
+    prelude.insert(
+        "str/join".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_range(args, 1, 2)?;
+
+            let (separator, coll) = match args {
+                [coll] => (String::new(), coll),
+                [separator, coll] => (to_str(separator), coll),
+                _ => unreachable!("join takes 1-2 arguments"),
+            };
+            let items: Vec<String> = seq_items(coll)?.iter().map(to_str).collect();
+
+            Ok(LanaExpr::String(items.join(&separator)))
+        }),
+    );
+
+    prelude.insert(
+        "str/trim".to_string(),
+        LanaExpr::Func(|args| map_string(args, |s| s.trim().to_string())),
+    );
+
+    prelude.insert(
+        "str/upper-case".to_string(),
+        LanaExpr::Func(|args| map_string(args, str::to_uppercase)),
+    );
+
+    prelude.insert(
+        "str/lower-case".to_string(),
+        LanaExpr::Func(|args| map_string(args, str::to_lowercase)),
+    );
+
+    prelude.insert(
+        "str/starts-with?".to_string(),
+        LanaExpr::Func(|args| test_strings(args, |s, prefix| s.starts_with(prefix))),
+    );
+
+    prelude.insert(
+        "str/ends-with?".to_string(),
+        LanaExpr::Func(|args| test_strings(args, |s, suffix| s.ends_with(suffix))),
+    );
+
+    prelude.insert(
+        "str/includes?".to_string(),
+        LanaExpr::Func(|args| test_strings(args, |s, substring| s.contains(substring))),
+    );
+
+    prelude.insert(
+        "str/replace".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 3)?;
+
+            let s = String::from_lana(&args[0])?;
+            let from = String::from_lana(&args[1])?;
+            let to = String::from_lana(&args[2])?;
+
+            Ok(LanaExpr::String(s.replace(&from, &to)))
+        }),
+    );
+
+    prelude.insert(
+        "str/index-of".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 2)?;
+
+            let s = String::from_lana(&args[0])?;
+            let substring = String::from_lana(&args[1])?;
+
+            // Indexes count characters, like `subs`, not bytes
+            Ok(match s.find(&substring) {
+                Some(byte) => LanaExpr::int(s[..byte].chars().count() as i64),
+                None => LanaExpr::Nil,
+            })
+        }),
+    );
+
+    prelude.insert(
+        "str/char-count".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let s = String::from_lana(&args[0])?;
+
+            Ok(LanaExpr::int(s.chars().count() as i64))
+        }),
+    );
+
+    prelude.insert(
+        "str/grapheme-count".to_string(),
+        LanaExpr::Func(|args| {
+            expect_args_count(args, 1)?;
+
+            let s = String::from_lana(&args[0])?;
+
+            Ok(LanaExpr::int(s.graphemes(true).count() as i64))
+        }),
+    );
+}
+
+/// Strings are used as they are and `nil` as an empty string, while other values are printed.
+pub fn to_str(expr: &LanaExpr) -> String {
+    match expr {
+        LanaExpr::Nil => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn strings(items: Vec<String>) -> LanaExpr {
+    LanaExpr::Vector(items.into_iter().map(LanaExpr::String).collect())
+}
+
+fn map_string(args: &[LanaExpr], f: impl Fn(&str) -> String) -> Result<LanaExpr, LanaErr> {
+    expect_args_count(args, 1)?;
+
+    Ok(LanaExpr::String(f(&String::from_lana(&args[0])?)))
+}
+
+fn test_strings(args: &[LanaExpr], f: impl Fn(&str, &str) -> bool) -> Result<LanaExpr, LanaErr> {
+    expect_args_count(args, 2)?;
+
+    let a = String::from_lana(&args[0])?;
+    let b = String::from_lana(&args[1])?;
+
+    Ok(LanaExpr::Bool(f(&a, &b)))
+}
+
+/// A `%` directive of a format string, like `%-5s` or `%08.3f`.
+#[derive(Default)]
+struct Directive {
+    left_align: bool,
+    zero_pad: bool,
+    plus_sign: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+/// Formats `args` printf-style. Supports `%s`, `%d`, `%f`, `%e`, `%x`, `%X`, `%o`, `%b` and `%%`,
+/// with the `-`, `0` and `+` flags, a width and a precision.
+fn format(template: &str, args: &[LanaExpr]) -> Result<String, LanaErr> {
+    let mut result = String::new();
+    let mut args = args.iter();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+
+        let mut directive = Directive::default();
+        while let Some(flag) = chars.next_if(|c| matches!(c, '-' | '0' | '+')) {
+            match flag {
+                '-' => directive.left_align = true,
+                '0' => directive.zero_pad = true,
+                _ => directive.plus_sign = true,
+            }
+        }
+        directive.width = read_digits(&mut chars);
+        if chars.next_if_eq(&'.').is_some() {
+            directive.precision = Some(read_digits(&mut chars).unwrap_or(0));
+        }
+
+        let conversion = chars
+            .next()
+            .ok_or_else(|| LanaErr::Reason("Format string ends with an incomplete '%'".into()))?;
+        if conversion == '%' {
+            result.push('%');
+            continue;
+        }
+
+        let arg = args.next().ok_or_else(|| {
+            LanaErr::Reason(format!(
+                "Not enough arguments for format directive '%{}'",
+                conversion
+            ))
+        })?;
+        let text = match conversion {
+            's' => match directive.precision {
+                Some(precision) => to_str(arg).chars().take(precision).collect(),
+                None => to_str(arg),
+            },
+            'd' => signed(integer(arg)?.to_string(), &directive),
+            'x' => signed(integer(arg)?.to_str_radix(16), &directive),
+            'X' => signed(integer(arg)?.to_str_radix(16).to_uppercase(), &directive),
+            'o' => signed(integer(arg)?.to_str_radix(8), &directive),
+            'b' => signed(integer(arg)?.to_str_radix(2), &directive),
+            'f' => {
+                let precision = directive.precision.unwrap_or(6);
+                signed(
+                    format!("{:.*}", precision, f64::from_lana(arg)?),
+                    &directive,
+                )
+            }
+            'e' => {
+                let precision = directive.precision.unwrap_or(6);
+                let n = format!("{:.*e}", precision, f64::from_lana(arg)?);
+                signed(c_exponent(&n), &directive)
+            }
+            other => {
+                return Err(LanaErr::Reason(format!(
+                    "Unknown format directive '%{}'",
+                    other
+                )))
+            }
+        };
+
+        result.push_str(&pad(text, &directive, conversion != 's'));
+    }
+
+    Ok(result)
+}
+
+fn read_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+
+    digits.parse().ok()
+}
+
+fn integer(expr: &LanaExpr) -> Result<num_bigint::BigInt, LanaErr> {
+    match expr {
+        LanaExpr::Number(n) => n.to_bigint(),
+        _ => None,
+    }
+    .ok_or_else(|| LanaErr::TypeError(format!("Expected an integer, got {:?}", expr)))
+}
+
+/// Writes exponents like C does, with a sign and at least two digits: `1.5e3` becomes `1.5e+03`.
+fn c_exponent(n: &str) -> String {
+    match n.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let (sign, digits) = match exponent.strip_prefix('-') {
+                Some(digits) => ('-', digits),
+                None => ('+', exponent),
+            };
+
+            format!("{}e{}{:0>2}", mantissa, sign, digits)
+        }
+        // NaN and infinity have no exponent
+        None => n.to_string(),
+    }
+}
+
+fn signed(number: String, directive: &Directive) -> String {
+    if directive.plus_sign && !number.starts_with('-') {
+        format!("+{}", number)
+    } else {
+        number
+    }
+}
+
+fn pad(text: String, directive: &Directive, is_number: bool) -> String {
+    let len = text.chars().count();
+    let fill = match directive.width {
+        Some(width) if width > len => width - len,
+        _ => return text,
+    };
+
+    if directive.left_align {
+        text + &" ".repeat(fill)
+    } else if directive.zero_pad && is_number {
+        // Zeros go between the sign and the digits
+        let sign_len = if text.starts_with(['-', '+']) { 1 } else { 0 };
+        let (sign, digits) = text.split_at(sign_len);
+
+        format!("{}{}{}", sign, "0".repeat(fill), digits)
+    } else {
+        " ".repeat(fill) + &text
+    }
+}
@@ -1,16 +1,166 @@
-use super::Token;
+use super::{LanaExpr, SrcSpan, Token};
+use std::path::Path;
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum LanaErr {
     Reason(String),
+    TypeError(String),
+    ArityError(String),
+    UndefinedSymbol(String),
+    IoError(String),
+    /// A string that doesn't hold what it was expected to, like a number.
+    ParseError(String),
+    /// An index past the end of a sequence or a string.
+    IndexError(String),
+    /// An arithmetic operation that has no result, like an exact division by zero.
+    ArithmeticError(String),
+    Thrown(Rc<LanaError>),
     UnexpectedToken(Token),
     UnterminatedExpr((char, Token)),
+    /// An error raised while evaluating the form read from the given span.
+    Located(Box<LanaErr>, SrcSpan),
+    /// An error along with the Lana functions it propagated out of, most recent call first.
+    Traced(Box<LanaErr>, Vec<StackFrame>),
+}
+
+impl LanaErr {
+    /// Converts this error into the value bound by a `catch` clause. Errors raised with `throw`
+    /// are caught as they were thrown.
+    pub fn to_error_value(&self) -> Rc<LanaError> {
+        let kind = match self {
+            LanaErr::Located(error, span) => {
+                let error = error.to_error_value();
+
+                return match error.span {
+                    Some(_) => error,
+                    None => Rc::new(LanaError {
+                        span: Some(span.clone()),
+                        ..(*error).clone()
+                    }),
+                };
+            }
+            LanaErr::Traced(error, _) => return error.to_error_value(),
+            LanaErr::Thrown(error) => return error.clone(),
+            LanaErr::Reason(_) => ":error",
+            LanaErr::TypeError(_) => ":type-error",
+            LanaErr::ArityError(_) => ":arity-error",
+            LanaErr::UndefinedSymbol(_) => ":undefined-symbol",
+            LanaErr::IoError(_) => ":io-error",
+            LanaErr::ParseError(_) => ":parse-error",
+            LanaErr::IndexError(_) => ":index-error",
+            LanaErr::ArithmeticError(_) => ":arithmetic-error",
+            LanaErr::UnexpectedToken(_) | LanaErr::UnterminatedExpr(_) => ":syntax-error",
+        };
+
+        Rc::new(LanaError {
+            kind: kind.into(),
+            message: self.to_string(),
+            data: LanaExpr::Nil,
+            span: None,
+        })
+    }
+
+    /// Where in the source this error was raised, if known.
+    pub fn span(&self) -> Option<SrcSpan> {
+        match self {
+            LanaErr::Located(_, span) => Some(span.clone()),
+            LanaErr::Traced(error, _) => error.span(),
+            LanaErr::Thrown(error) => error.span.clone(),
+            LanaErr::UnexpectedToken(token) => Some(token.span()),
+            LanaErr::UnterminatedExpr((_, token)) => Some(token.span()),
+            _ => None,
+        }
+    }
+
+    /// Attaches `span` to this error, unless it already points at a more precise location.
+    pub fn located(self, span: &SrcSpan) -> LanaErr {
+        match self {
+            LanaErr::Traced(error, frames) => {
+                LanaErr::Traced(Box::new(error.located(span)), frames)
+            }
+            error if error.span().is_some() => error,
+            error => LanaErr::Located(Box::new(error), span.clone()),
+        }
+    }
+
+    /// Records that this error propagated out of a call to a Lana function.
+    pub fn with_frame(self, frame: StackFrame) -> LanaErr {
+        match self {
+            LanaErr::Traced(error, mut frames) => {
+                frames.push(frame);
+
+                LanaErr::Traced(error, frames)
+            }
+            error => LanaErr::Traced(Box::new(error), vec![frame]),
+        }
+    }
+
+    /// Records that the spans of this error and of its stack frames that aren't known to be in
+    /// another file are in `file`, as the error propagates out of it.
+    pub fn in_file(self, file: &Rc<Path>) -> LanaErr {
+        match self {
+            LanaErr::Located(error, span) => {
+                LanaErr::Located(Box::new(error.in_file(file)), span.in_file(file))
+            }
+            LanaErr::Traced(error, frames) => {
+                let frames = frames
+                    .into_iter()
+                    .map(|frame| StackFrame {
+                        call_site: frame.call_site.map(|span| span.in_file(file)),
+                        ..frame
+                    })
+                    .collect();
+
+                LanaErr::Traced(Box::new(error.in_file(file)), frames)
+            }
+            LanaErr::Thrown(error) => LanaErr::Thrown(Rc::new(LanaError {
+                span: error.span.clone().map(|span| span.in_file(file)),
+                ..(*error).clone()
+            })),
+            LanaErr::UnexpectedToken(_) | LanaErr::UnterminatedExpr(_) => {
+                let span = self.span().map(|span| span.in_file(file));
+
+                match span {
+                    Some(span) => LanaErr::Located(Box::new(self), span),
+                    None => self,
+                }
+            }
+            error => error,
+        }
+    }
+
+    pub fn stack_trace(&self) -> &[StackFrame] {
+        match self {
+            LanaErr::Traced(_, frames) => frames,
+            _ => &[],
+        }
+    }
+
+    #[cfg(test)]
+    pub fn without_context(self) -> LanaErr {
+        match self {
+            LanaErr::Located(error, _) | LanaErr::Traced(error, _) => error.without_context(),
+            error => error,
+        }
+    }
 }
 
 impl std::fmt::Display for LanaErr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let string = match self {
             LanaErr::Reason(msg) => msg.to_string(),
+            LanaErr::TypeError(msg) => msg.to_string(),
+            LanaErr::ArityError(msg) => msg.to_string(),
+            LanaErr::UndefinedSymbol(symbol) => format!("Undefined symbol '{}'", symbol),
+            LanaErr::IoError(msg) => msg.to_string(),
+            LanaErr::ParseError(msg) => msg.to_string(),
+            LanaErr::IndexError(msg) => msg.to_string(),
+            LanaErr::ArithmeticError(msg) => msg.to_string(),
+            LanaErr::Thrown(error) => error.message.clone(),
+            LanaErr::Located(error, _) => error.to_string(),
+            LanaErr::Traced(error, _) => error.to_string(),
             LanaErr::UnexpectedToken(token) => format!("unexpected {:?}", token),
             LanaErr::UnterminatedExpr((expected, opening_token)) => {
                 format!(
@@ -23,3 +173,29 @@ impl std::fmt::Display for LanaErr {
         write!(f, "{}", string)
     }
 }
+
+/// A call to a Lana function. `name` is `None` for anonymous functions, and `call_site` for
+/// functions called from builtins, like the one given to `map`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    pub name: Option<String>,
+    pub call_site: Option<SrcSpan>,
+}
+
+/// An error as a Lana value, created with `ex-info` or bound by a `catch` clause. Its kind is a
+/// keyword like `:type-error`, which `catch` clauses can filter on.
+#[derive(Debug, Clone)]
+pub struct LanaError {
+    pub kind: String,
+    pub message: String,
+    pub data: LanaExpr,
+    /// Where the error was raised. Filled in as it propagates out of a source form.
+    pub span: Option<SrcSpan>,
+}
+
+/// Errors are compared by value, regardless of where they were raised.
+impl PartialEq for LanaError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.message == other.message && self.data == other.data
+    }
+}
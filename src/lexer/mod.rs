@@ -1,8 +1,9 @@
 mod src_location;
 mod token;
 
-pub use src_location::SrcLocation;
-pub use token::{Token, TokenKind};
+use super::LanaNumber;
+pub use src_location::{SrcLocation, SrcSpan};
+pub use token::{StringPart, Token, TokenKind};
 
 #[derive(Debug)]
 pub struct Tokenizer<'a> {
@@ -31,16 +32,44 @@ impl<'a> Tokenizer<'a> {
     fn next_token(&mut self) -> Option<Token> {
         self.skip_whitespaces();
 
-        self.next_char().and_then(|c| match c {
+        let c = self.next_char()?;
+        let start = self.loc();
+
+        let token = match c {
             '(' => Some(Token::new(TokenKind::LParen, self.loc())),
             ')' => Some(Token::new(TokenKind::RParen, self.loc())),
+            '[' => Some(Token::new(TokenKind::LBracket, self.loc())),
+            ']' => Some(Token::new(TokenKind::RBracket, self.loc())),
+            '{' => Some(Token::new(TokenKind::LBrace, self.loc())),
+            '}' => Some(Token::new(TokenKind::RBrace, self.loc())),
+            '#' if self.peek() == Some(&'{') => {
+                self.next_char();
+
+                Some(Token::new(TokenKind::HashLBrace, self.loc()))
+            }
+            '\'' => Some(Token::new(TokenKind::Quote, self.loc())),
+            '`' => Some(Token::new(TokenKind::Quasiquote, self.loc())),
+            '~' => Some(self.read_unquote()),
+            '@' => Some(Token::new(TokenKind::Deref, self.loc())),
             ';' => {
                 self.skip_line();
-                self.next_token()
+                return self.next_token();
+            }
+            '"' => Some(self.read_string(false)),
+            '#' if self.peek() == Some(&'"') => {
+                self.next_char();
+
+                Some(self.read_regex())
+            }
+            '$' if self.peek() == Some(&'"') => {
+                self.next_char();
+
+                Some(self.read_string(true))
             }
-            '"' => Some(self.read_string()),
             c => Some(self.read_id_or_number(c)),
-        })
+        };
+
+        token.map(|token| token.starting_at(start))
     }
 
     fn skip_whitespaces(&mut self) {
@@ -53,44 +82,120 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
-    fn read_string(&mut self) -> Token {
+    /// Reads a string after its opening quote. Interpolated strings may embed forms with `#{...}`,
+    /// which are read as tokens, so they know where in the string they are.
+    fn read_string(&mut self, interpolated: bool) -> Token {
         let mut string: String = String::new();
-        let token: Token;
+        let mut parts = vec![];
 
         loop {
             match self.next_char() {
-                Some('"') => {
-                    token = Token::new(TokenKind::String(string), self.loc());
-                    break;
-                }
-                Some(c) => match c {
-                    '\\' => match self.next_char() {
-                        Some('n') => string.push('\n'),
-                        Some('t') => string.push('\t'),
-                        Some('"') => string.push('"'),
-                        Some(c) => string.push(c),
-                        None => continue,
-                    },
-                    _ => string.push(c),
+                Some('"') => break,
+                Some('\\') => match self.next_char() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('"') => string.push('"'),
+                    Some(c) => string.push(c),
+                    None => continue,
                 },
-                None => {
-                    let mut s = String::from("\"");
-                    s.push_str(&string); // prepending " on unterminated strings
-
-                    token = Token::new(TokenKind::UnterminatedString(s), self.loc());
-                    break;
+                Some('#') if interpolated && self.peek() == Some(&'{') => {
+                    let start = self.loc();
+                    self.next_char();
+
+                    if !string.is_empty() {
+                        parts.push(StringPart::Text(std::mem::take(&mut string)));
+                    }
+
+                    match self.read_embedded_form() {
+                        Some(tokens) => {
+                            let span = SrcSpan::new(start, self.loc());
+                            parts.push(StringPart::Code(tokens, span));
+                        }
+                        None => return self.unterminated_string(string),
+                    }
                 }
+                Some(c) => string.push(c),
+                None => return self.unterminated_string(string),
             };
         }
 
-        token
+        if !interpolated {
+            return Token::new(TokenKind::String(string), self.loc());
+        }
+
+        if !string.is_empty() {
+            parts.push(StringPart::Text(string));
+        }
+
+        Token::new(TokenKind::InterpolatedString(parts), self.loc())
+    }
+
+    /// Reads a regex after its opening quote. Backslashes are kept for the regex, so `#"\d"` matches
+    /// a digit, except in `\"`, which is a quote.
+    fn read_regex(&mut self) -> Token {
+        let mut pattern = String::new();
+
+        loop {
+            match self.next_char() {
+                Some('"') => return Token::new(TokenKind::Regex(pattern), self.loc()),
+                Some('\\') if self.peek() == Some(&'"') => {
+                    self.next_char();
+                    pattern.push('"');
+                }
+                Some('\\') => {
+                    pattern.push('\\');
+                    if let Some(c) = self.next_char() {
+                        pattern.push(c);
+                    }
+                }
+                Some(c) => pattern.push(c),
+                None => return self.unterminated_string(pattern),
+            }
+        }
+    }
+
+    /// Reads the tokens of a form embedded in an interpolated string, up to its closing `}`.
+    /// Returns `None` if the source ends first.
+    fn read_embedded_form(&mut self) -> Option<Vec<Token>> {
+        let mut tokens = vec![];
+        let mut depth = 0;
+
+        loop {
+            let token = self.next_token()?;
+
+            match token.kind {
+                TokenKind::RBrace if depth == 0 => return Some(tokens),
+                TokenKind::LBrace | TokenKind::HashLBrace => depth += 1,
+                TokenKind::RBrace => depth -= 1,
+                _ => {}
+            }
+
+            tokens.push(token);
+        }
+    }
+
+    fn unterminated_string(&self, string: String) -> Token {
+        let mut s = String::from("\"");
+        s.push_str(&string); // prepending " on unterminated strings
+
+        Token::new(TokenKind::UnterminatedString(s), self.loc())
+    }
+
+    fn read_unquote(&mut self) -> Token {
+        if let Some('@') = self.peek() {
+            self.next_char();
+
+            Token::new(TokenKind::SpliceUnquote, self.loc())
+        } else {
+            Token::new(TokenKind::Unquote, self.loc())
+        }
     }
 
     fn read_id_or_number(&mut self, begin: char) -> Token {
         let mut token: String = String::from(begin);
 
         loop {
-            let is_separator = self.src.peek().map_or(true, |c| Self::is_separator(*c));
+            let is_separator = self.src.peek().is_none_or(|c| Self::is_separator(*c));
 
             if is_separator {
                 break;
@@ -100,9 +205,9 @@ impl<'a> Tokenizer<'a> {
             }
         }
 
-        match token.parse::<f64>() {
-            Ok(n) => Token::new(TokenKind::Number(n), self.loc()),
-            Err(_) => Token::new(TokenKind::Id(token), self.loc()),
+        match LanaNumber::parse(&token) {
+            Some(n) => Token::new(TokenKind::Number(n), self.loc()),
+            None => Token::new(TokenKind::Id(token), self.loc()),
         }
     }
 
@@ -144,7 +249,7 @@ impl<'a> Tokenizer<'a> {
 
     fn is_separator(c: char) -> bool {
         match c {
-            '(' | ')' | ';' => true,
+            '(' | ')' | '[' | ']' | '{' | '}' | ';' => true,
             _ => c.is_whitespace(),
         }
     }
@@ -175,8 +280,14 @@ mod tests {
             vec![
                 Token::new(TokenKind::LParen, SrcLocation::new(1, 1)),
                 Token::new(TokenKind::Id("+".to_string()), SrcLocation::new(1, 2)),
-                Token::new(TokenKind::Number(1.0), SrcLocation::new(1, 4)),
-                Token::new(TokenKind::Number(2.0), SrcLocation::new(1, 6)),
+                Token::new(
+                    TokenKind::Number(LanaNumber::Int(1)),
+                    SrcLocation::new(1, 4)
+                ),
+                Token::new(
+                    TokenKind::Number(LanaNumber::Int(2)),
+                    SrcLocation::new(1, 6)
+                ),
                 Token::new(TokenKind::RParen, SrcLocation::new(2, 2)),
             ],
             tokens,
@@ -216,7 +327,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::RParen,
-                loc: SrcLocation { line: 2, col: 1 }
+                loc: SrcLocation { line: 2, col: 1 },
+                start: SrcLocation { line: 2, col: 1 }
             },
             token
         );
@@ -244,7 +356,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::LParen,
-                loc: SrcLocation { line: 1, col: 1 }
+                loc: SrcLocation { line: 1, col: 1 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -261,7 +374,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::RParen,
-                loc: SrcLocation { line: 1, col: 1 }
+                loc: SrcLocation { line: 1, col: 1 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -278,7 +392,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::String("hello world!".to_string()),
-                loc: SrcLocation { line: 1, col: 14 }
+                loc: SrcLocation { line: 1, col: 14 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -295,7 +410,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::String("hello\nworld\t  \"!".to_string()),
-                loc: SrcLocation { line: 1, col: 22 }
+                loc: SrcLocation { line: 1, col: 22 },
+                start: SrcLocation { line: 1, col: 2 }
             },
             token
         );
@@ -312,7 +428,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::String("helloçworld!".to_string()),
-                loc: SrcLocation { line: 1, col: 15 }
+                loc: SrcLocation { line: 1, col: 15 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -329,7 +446,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::UnterminatedString("\"hello".to_string()),
-                loc: SrcLocation { line: 1, col: 7 }
+                loc: SrcLocation { line: 1, col: 7 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -345,8 +463,9 @@ mod tests {
 
         assert_eq!(
             Token {
-                kind: TokenKind::Number(123.0),
-                loc: SrcLocation { line: 1, col: 3 }
+                kind: TokenKind::Number(LanaNumber::Int(123)),
+                loc: SrcLocation { line: 1, col: 3 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -362,8 +481,9 @@ mod tests {
 
         assert_eq!(
             Token {
-                kind: TokenKind::Number(123.123),
-                loc: SrcLocation { line: 1, col: 7 }
+                kind: TokenKind::Number(LanaNumber::Float(123.123)),
+                loc: SrcLocation { line: 1, col: 7 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -379,13 +499,85 @@ mod tests {
 
         assert_eq!(
             Token {
-                kind: TokenKind::Number(-123.0),
-                loc: SrcLocation { line: 1, col: 4 }
+                kind: TokenKind::Number(LanaNumber::Int(-123)),
+                loc: SrcLocation { line: 1, col: 4 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
     }
 
+    #[test]
+    fn it_parses_number_literals() {
+        let kinds: Vec<TokenKind> = Tokenizer::new("1/3 0x1F 0b101 1_000_000 -2/4 1e3")
+            .tokens()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+
+        assert_eq!(
+            vec![
+                TokenKind::Number(LanaNumber::parse("1/3").unwrap()),
+                TokenKind::Number(LanaNumber::Int(31)),
+                TokenKind::Number(LanaNumber::Int(5)),
+                TokenKind::Number(LanaNumber::Int(1_000_000)),
+                TokenKind::Number(LanaNumber::parse("-1/2").unwrap()),
+                TokenKind::Number(LanaNumber::Float(1000.0)),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn it_parses_number_like_ids() {
+        let kinds: Vec<TokenKind> = Tokenizer::new("1/0 0xZ -> +")
+            .tokens()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+
+        assert_eq!(
+            vec![
+                TokenKind::Id("1/0".into()),
+                TokenKind::Id("0xZ".into()),
+                TokenKind::Id("->".into()),
+                TokenKind::Id("+".into()),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn it_reads_forms_embedded_in_interpolated_strings() {
+        let token = Tokenizer::new("$\"a #{x}\"")
+            .next_token()
+            .expect("failed to lex interpolated string");
+
+        assert_eq!(
+            TokenKind::InterpolatedString(vec![
+                StringPart::Text("a ".into()),
+                StringPart::Code(
+                    vec![Token {
+                        kind: TokenKind::Id("x".into()),
+                        loc: SrcLocation::new(1, 7),
+                        start: SrcLocation::new(1, 7),
+                    }],
+                    SrcSpan::new(SrcLocation::new(1, 5), SrcLocation::new(1, 8))
+                ),
+            ]),
+            token.kind
+        );
+    }
+
+    #[test]
+    fn it_keeps_backslashes_in_regexes() {
+        let token = Tokenizer::new(r#"#"\d+\"""#)
+            .next_token()
+            .expect("failed to lex regex");
+
+        assert_eq!(TokenKind::Regex(r#"\d+""#.into()), token.kind);
+    }
+
     #[test]
     fn it_stop_parsing_ids_on_whitespace() {
         let input = "someId other".to_string();
@@ -397,7 +589,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::Id("someId".to_string()),
-                loc: SrcLocation { line: 1, col: 6 }
+                loc: SrcLocation { line: 1, col: 6 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -414,7 +607,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::Id("someId".to_string()),
-                loc: SrcLocation { line: 1, col: 6 }
+                loc: SrcLocation { line: 1, col: 6 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -431,7 +625,8 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::Id("someId".to_string()),
-                loc: SrcLocation { line: 1, col: 6 }
+                loc: SrcLocation { line: 1, col: 6 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
@@ -448,9 +643,97 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::Id("someId".to_string()),
-                loc: SrcLocation { line: 1, col: 6 }
+                loc: SrcLocation { line: 1, col: 6 },
+                start: SrcLocation { line: 1, col: 1 }
             },
             token
         );
     }
+
+    #[test]
+    fn it_lexes_quotes() {
+        let input = "'a `b ~c ~@d".to_string();
+
+        let tokens = Tokenizer::new(&input).tokens();
+
+        assert_eq!(
+            vec![
+                Token::new(TokenKind::Quote, SrcLocation::new(1, 1)),
+                Token::new(TokenKind::Id("a".to_string()), SrcLocation::new(1, 2)),
+                Token::new(TokenKind::Quasiquote, SrcLocation::new(1, 4)),
+                Token::new(TokenKind::Id("b".to_string()), SrcLocation::new(1, 5)),
+                Token::new(TokenKind::Unquote, SrcLocation::new(1, 7)),
+                Token::new(TokenKind::Id("c".to_string()), SrcLocation::new(1, 8)),
+                Token::new(TokenKind::SpliceUnquote, SrcLocation::new(1, 11))
+                    .starting_at(SrcLocation::new(1, 10)),
+                Token::new(TokenKind::Id("d".to_string()), SrcLocation::new(1, 12)),
+            ],
+            tokens,
+        );
+    }
+
+    #[test]
+    fn it_lexes_derefs() {
+        let input = "@a".to_string();
+
+        let tokens = Tokenizer::new(&input).tokens();
+
+        assert_eq!(
+            vec![
+                Token::new(TokenKind::Deref, SrcLocation::new(1, 1)),
+                Token::new(TokenKind::Id("a".to_string()), SrcLocation::new(1, 2)),
+            ],
+            tokens,
+        );
+    }
+
+    #[test]
+    fn it_allows_quotes_inside_ids() {
+        let input = "times'".to_string();
+
+        let token = Tokenizer::new(&input)
+            .next_token()
+            .expect("failed to lex id");
+
+        assert_eq!(TokenKind::Id("times'".to_string()), token.kind);
+    }
+
+    #[test]
+    fn it_lexes_collection_delimiters() {
+        let input = "[a] {:b #{}}".to_string();
+
+        let tokens = Tokenizer::new(&input).tokens();
+
+        assert_eq!(
+            vec![
+                Token::new(TokenKind::LBracket, SrcLocation::new(1, 1)),
+                Token::new(TokenKind::Id("a".to_string()), SrcLocation::new(1, 2)),
+                Token::new(TokenKind::RBracket, SrcLocation::new(1, 3)),
+                Token::new(TokenKind::LBrace, SrcLocation::new(1, 5)),
+                Token::new(TokenKind::Id(":b".to_string()), SrcLocation::new(1, 7))
+                    .starting_at(SrcLocation::new(1, 6)),
+                Token::new(TokenKind::HashLBrace, SrcLocation::new(1, 10))
+                    .starting_at(SrcLocation::new(1, 9)),
+                Token::new(TokenKind::RBrace, SrcLocation::new(1, 11)),
+                Token::new(TokenKind::RBrace, SrcLocation::new(1, 12)),
+            ],
+            tokens,
+        );
+    }
+
+    #[test]
+    fn it_records_where_tokens_start() {
+        let input = "(foo \"a\nb\")".to_string();
+
+        let tokens = Tokenizer::new(&input).tokens();
+
+        assert_eq!(
+            SrcSpan::new(SrcLocation::new(1, 2), SrcLocation::new(1, 4)),
+            tokens[1].span()
+        );
+        assert_eq!(
+            SrcSpan::new(SrcLocation::new(1, 6), SrcLocation::new(2, 2)),
+            tokens[2].span()
+        );
+    }
 }
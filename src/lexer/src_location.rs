@@ -1,14 +1,20 @@
+use std::path::Path;
+use std::rc::Rc;
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct SrcLocation {
     pub line: i32,
     pub col: i32,
 }
 
-impl SrcLocation {
-    pub fn default() -> Self {
+/// Where the tokenizer starts, right before the first character.
+impl Default for SrcLocation {
+    fn default() -> Self {
         SrcLocation { line: 1, col: 0 }
     }
+}
 
+impl SrcLocation {
     #[cfg(test)]
     pub fn new(line: i32, col: i32) -> Self {
         SrcLocation { line, col }
@@ -20,3 +26,38 @@ impl std::fmt::Display for SrcLocation {
         write!(f, "line {}, column {}", self.line, self.col)
     }
 }
+
+/// The stretch of source between two locations, both inclusive.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SrcSpan {
+    pub start: SrcLocation,
+    pub end: SrcLocation,
+    /// The file the source was read from. Only known for spans of errors that propagated out of a
+    /// file, see `LanaErr::in_file`.
+    pub file: Option<Rc<Path>>,
+}
+
+impl SrcSpan {
+    pub fn new(start: SrcLocation, end: SrcLocation) -> Self {
+        SrcSpan {
+            start,
+            end,
+            file: None,
+        }
+    }
+
+    /// This span, in `file` unless its file is already known.
+    pub fn in_file(mut self, file: &Rc<Path>) -> Self {
+        if self.file.is_none() {
+            self.file = Some(file.clone());
+        }
+
+        self
+    }
+}
+
+impl std::fmt::Display for SrcSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
@@ -1,14 +1,29 @@
-use super::SrcLocation;
+use super::{LanaNumber, SrcLocation, SrcSpan};
 
+/// A token read from the source. `loc` points at its last character and
+/// `start` at its first one.
 #[derive(PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub loc: SrcLocation,
+    pub start: SrcLocation,
 }
 
 impl Token {
     pub fn new(kind: TokenKind, loc: SrcLocation) -> Self {
-        Self { kind, loc }
+        Self {
+            kind,
+            start: loc.clone(),
+            loc,
+        }
+    }
+
+    pub fn starting_at(self, start: SrcLocation) -> Self {
+        Self { start, ..self }
+    }
+
+    pub fn span(&self) -> SrcSpan {
+        SrcSpan::new(self.start.clone(), self.loc.clone())
     }
 }
 
@@ -21,11 +36,34 @@ impl std::fmt::Debug for Token {
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenKind {
     String(String),
-    Number(f64),
+    Number(LanaNumber),
     LParen,
     RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    HashLBrace,
+    Quote,
+    Quasiquote,
+    Unquote,
+    SpliceUnquote,
+    /// `@`, which reads as a call to `deref`.
+    Deref,
     Id(String),
     UnterminatedString(String),
+    /// The pattern of a `#"..."` regex.
+    Regex(String),
+    /// A `$"..."` string, with the forms embedded in it by `#{...}`.
+    InterpolatedString(Vec<StringPart>),
+}
+
+/// A piece of an interpolated string: either plain text, or the tokens of an embedded form and
+/// where its `#{...}` is.
+#[derive(PartialEq, Debug, Clone)]
+pub enum StringPart {
+    Text(String),
+    Code(Vec<Token>, SrcSpan),
 }
 
 impl std::fmt::Display for TokenKind {
@@ -35,8 +73,20 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Number(n) => format!("'{}'", n),
             TokenKind::Id(k) => format!("'{}'", k),
             TokenKind::UnterminatedString(token) => format!("'{}'", token),
+            TokenKind::Regex(pattern) => format!("'#\"{}\"'", pattern),
+            TokenKind::InterpolatedString(_) => "interpolated string".to_string(),
             TokenKind::LParen => "'('".to_string(),
             TokenKind::RParen => "')'".to_string(),
+            TokenKind::LBracket => "'['".to_string(),
+            TokenKind::RBracket => "']'".to_string(),
+            TokenKind::LBrace => "'{'".to_string(),
+            TokenKind::RBrace => "'}'".to_string(),
+            TokenKind::HashLBrace => "'#{'".to_string(),
+            TokenKind::Quote => "'''".to_string(),
+            TokenKind::Quasiquote => "'`'".to_string(),
+            TokenKind::Unquote => "'~'".to_string(),
+            TokenKind::SpliceUnquote => "'~@'".to_string(),
+            TokenKind::Deref => "'@'".to_string(),
         };
 
         write!(f, "{}", string)
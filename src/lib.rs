@@ -0,0 +1,226 @@
+//! Lana, a LISP built in Rust, as a library to embed in other programs.
+//!
+//! ```
+//! use lana::{Interpreter, LanaExpr};
+//!
+//! let lana = Interpreter::new();
+//! lana.eval_str("(defn double (x) (* x 2))").unwrap();
+//!
+//! let result = lana.call("double", &[LanaExpr::int(21)]).unwrap();
+//! assert_eq!(LanaExpr::int(42), result);
+//! ```
+//!
+//! # Stability
+//!
+//! [`Interpreter`] and its methods are the supported way of embedding Lana and follow semantic
+//! versioning: they won't change in a breaking way without a major version bump. [`LanaExpr`] and
+//! [`LanaErr`] are `#[non_exhaustive]`, as new values and errors are added in minor versions, and
+//! the messages of errors aren't part of the API. Everything else exported from here is only
+//! public because it shows up in those types, and may change at any time.
+
+mod convert;
+mod interpreter;
+mod lana_err;
+mod lexer;
+mod number;
+mod parser;
+#[cfg(feature = "serde")]
+mod serialization;
+
+pub use convert::{FromLana, IntoLana, IntoLanaResult, IntoNativeFn};
+pub use interpreter::LanaEnv;
+pub use lana_err::{LanaErr, LanaError, StackFrame};
+use lexer::Tokenizer;
+pub use lexer::{SrcLocation, SrcSpan, StringPart, Token, TokenKind};
+pub use number::LanaNumber;
+pub use parser::{
+    LanaArity, LanaAtom, LanaExpr, LanaLambda, LanaRecord, LanaRecordType, LanaRegex, ListSpans,
+    NativeFn,
+};
+use std::path::Path;
+
+/// A Lana interpreter. Each one has its own global environment, starting with the prelude.
+pub struct Interpreter {
+    env: LanaEnv,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            env: LanaEnv::default(),
+        }
+    }
+
+    /// Evaluates every form in `src`, returning the value of the last one, or `nil` if there are
+    /// none.
+    pub fn eval_str(&self, src: &str) -> Result<LanaExpr, LanaErr> {
+        interpreter::eval_source(src, &self.env)
+    }
+
+    /// Evaluates the file at `path`. Modules it requires are looked for in its directory, then in
+    /// the directories listed in the `LANA_PATH` environment variable.
+    pub fn eval_file(&self, path: impl AsRef<Path>) -> Result<LanaExpr, LanaErr> {
+        interpreter::eval_file(path.as_ref(), &self.env)
+    }
+
+    /// Calls the function bound to `name` with already evaluated arguments.
+    pub fn call(&self, name: &str, args: &[LanaExpr]) -> Result<LanaExpr, LanaErr> {
+        let function = self
+            .get(name)
+            .ok_or_else(|| LanaErr::UndefinedSymbol(name.into()))?;
+
+        interpreter::apply(&function, args, &self.env)
+    }
+
+    pub fn define(&self, name: &str, value: LanaExpr) {
+        self.env.define(name, value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<LanaExpr> {
+        self.env.get(name)
+    }
+
+    /// Defines a function implemented in Rust under its name.
+    pub fn register(&self, function: NativeFn) {
+        self.env.register(function);
+    }
+}
+
+/// Frees the global environment and the loaded modules. Functions defined in Lana and still held
+/// after the interpreter is dropped fail to find the globals they use.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        self.env.clear();
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_evaluates_strings_to_their_last_value() {
+        let lana = Interpreter::new();
+
+        let result = lana.eval_str("(def x 2) (+ x 1)");
+
+        assert_eq!(Ok(LanaExpr::int(3)), result);
+    }
+
+    #[test]
+    fn it_evaluates_files() {
+        let lana = Interpreter::new();
+        let path = std::env::temp_dir().join("lana-it-evaluates-files.lana");
+        std::fs::write(&path, "(defn inc (x) (+ x 1))\n(inc 41)").unwrap();
+
+        let result = lana.eval_file(&path);
+
+        assert_eq!(Ok(LanaExpr::int(42)), result);
+        assert!(lana.get("inc").is_some());
+    }
+
+    #[test]
+    fn it_errors_on_missing_files() {
+        let lana = Interpreter::new();
+
+        let result = lana.eval_file("examples/missing.lana");
+
+        assert!(matches!(result, Err(LanaErr::IoError(_))));
+    }
+
+    #[test]
+    fn it_calls_lana_functions_by_name() {
+        let lana = Interpreter::new();
+        lana.eval_str("(defn add (a b) (+ a b))").unwrap();
+
+        let result = lana.call("add", &[LanaExpr::int(1), LanaExpr::int(2)]);
+
+        assert_eq!(Ok(LanaExpr::int(3)), result);
+    }
+
+    #[test]
+    fn it_errors_when_calling_undefined_functions() {
+        let lana = Interpreter::new();
+
+        let result = lana.call("missing", &[]);
+
+        assert_eq!(Err(LanaErr::UndefinedSymbol("missing".into())), result);
+    }
+
+    #[test]
+    fn it_exposes_definitions_to_lana_code() {
+        let lana = Interpreter::new();
+
+        lana.define("answer", LanaExpr::int(42));
+
+        assert_eq!(Ok(LanaExpr::int(43)), lana.eval_str("(+ answer 1)"));
+    }
+
+    #[test]
+    fn it_registers_closures_as_native_functions() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let lana = Interpreter::new();
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+
+        lana.register(NativeFn::new("count!", move |_args| {
+            counter.set(counter.get() + 1);
+
+            Ok(LanaExpr::int(counter.get()))
+        }));
+        let result = lana.eval_str("(count!) (count!)");
+
+        assert_eq!(Ok(LanaExpr::int(2)), result);
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn it_checks_the_arity_of_native_functions() {
+        let lana = Interpreter::new();
+        lana.register(
+            NativeFn::new("pair", |args| Ok(LanaExpr::Vector(args.to_vec()))).arity(2, Some(2)),
+        );
+
+        let result = lana.eval_str("(pair 1)").map_err(|err| err.to_string());
+
+        assert_eq!(
+            Err("'pair' expected 2 argument(s), got 1".to_string()),
+            result
+        );
+    }
+
+    #[test]
+    fn it_prints_native_functions_by_name() {
+        let lana = Interpreter::new();
+        lana.register(NativeFn::new("noop", |_args| Ok(LanaExpr::Nil)).doc("Does nothing"));
+
+        assert_eq!("fn(noop)", lana.get("noop").unwrap().to_string());
+        assert_eq!("fn(+)", lana.get("+").unwrap().to_string());
+    }
+
+    #[test]
+    fn it_frees_its_environment_when_dropped() {
+        use std::rc::Rc;
+
+        let lana = Interpreter::new();
+        let marker = Rc::new(());
+        let freed = Rc::downgrade(&marker);
+
+        lana.register(NativeFn::new("marker", move |_args| {
+            Ok(LanaExpr::int(Rc::strong_count(&marker) as i64))
+        }));
+        // The lambda captures the frame it is defined in
+        lana.eval_str("(defn f () (marker)) (f)").unwrap();
+        drop(lana);
+
+        assert!(freed.upgrade().is_none());
+    }
+}
@@ -1,14 +1,6 @@
-mod interpreter;
-mod lana_err;
-mod lexer;
-mod parser;
 mod repl;
 
-use interpreter::LanaEnv;
-use lana_err::LanaErr;
-use lexer::Tokenizer;
-use lexer::{Token, TokenKind};
-use parser::{parse, LanaExpr, LanaLambda};
+use lana::{Interpreter, LanaErr, StackFrame};
 use repl::repl;
 use std::env;
 
@@ -28,39 +20,134 @@ fn main() {
 }
 
 fn run_file(filename: String) {
-    use std::fs;
+    let lana = Interpreter::new();
 
-    let input: String;
+    if let Err(error) = lana.eval_file(&filename) {
+        let src = std::fs::read_to_string(&filename).unwrap_or_default();
 
-    match fs::read_to_string(filename) {
-        Ok(content) => input = content,
-        Err(msg) => {
-            print_error(&msg);
-            return;
-        }
-    };
-
-    if let Err(error) = eval(input) {
-        print_error(error);
+        print_error(&error);
+        print_source_snippet(&error, &filename, &src);
+        print_stack_trace(&error, &filename);
     }
 }
 
-fn eval(input: String) -> Result<(), LanaErr> {
-    let tokens = lexer::Tokenizer::new(&input).tokens();
-    let exprs = parser::parse_all(&tokens)?;
+fn print_error(msg: impl std::fmt::Display) {
+    use colored::Colorize;
 
-    let mut env = LanaEnv::default();
-    for expr in exprs {
-        interpreter::eval(&expr, &mut env)?;
-    }
+    let s = format!("ERROR: {}.", msg).bold().red().to_string();
 
-    Ok(())
+    println!("{}", s);
 }
 
-fn print_error(msg: impl std::fmt::Display) {
+/// Prints the source line where `error` was raised, with its span underlined, like rustc does:
+///
+/// ```text
+///   --> examples/hello.lana:3:4
+///    |
+///  3 | (+ x 1)
+///    |    ^
+/// ```
+///
+/// `src` is the source of `filename`, which is where the error was raised unless its span says
+/// otherwise, like for errors raised in a loaded file.
+fn print_source_snippet(error: &LanaErr, filename: &str, src: &str) {
     use colored::Colorize;
 
-    let s = format!("ERROR: {}.", msg).bold().red().to_string();
+    let span = match error.span() {
+        Some(span) => span,
+        None => return,
+    };
+    let (filename, src) = match &span.file {
+        Some(file) => (
+            file.display().to_string(),
+            std::fs::read_to_string(file).unwrap_or_default(),
+        ),
+        None => (filename.to_string(), src.to_string()),
+    };
+    let line = match src.lines().nth((span.start.line - 1) as usize) {
+        Some(line) => line.replace('\t', " "),
+        None => return,
+    };
 
-    println!("{}", s);
+    let start = (span.start.col - 1).max(0) as usize;
+    let end = if span.end.line == span.start.line {
+        span.end.col as usize
+    } else {
+        line.chars().count()
+    };
+    let underline = format!(
+        "{}{}",
+        " ".repeat(start),
+        "^".repeat(end.saturating_sub(start).max(1))
+    );
+
+    let line_number = span.start.line.to_string();
+    let gutter = " ".repeat(line_number.len());
+    let bar = "|".blue().bold();
+
+    println!(
+        "{} {} {}:{}:{}",
+        gutter,
+        "-->".blue().bold(),
+        filename,
+        span.start.line,
+        span.start.col
+    );
+    println!("{}  {}", gutter, bar);
+    println!(" {} {} {}", line_number.blue().bold(), bar, line);
+    println!("{}  {} {}", gutter, bar, underline.red().bold());
+}
+
+const DEFAULT_TRACE_DEPTH: usize = 10;
+
+/// Prints the Lana functions `error` propagated out of, most recent call first. Consecutive calls
+/// from the same place, like those made by a recursive function, are printed once. At most
+/// `LANA_TRACE_DEPTH` lines are printed, 10 by default.
+fn print_stack_trace(error: &LanaErr, filename: &str) {
+    let frames = error.stack_trace();
+    if frames.is_empty() {
+        return;
+    }
+
+    let depth = env::var("LANA_TRACE_DEPTH")
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_TRACE_DEPTH);
+
+    let mut deduped: Vec<(&StackFrame, usize)> = vec![];
+    for frame in frames {
+        match deduped.last_mut() {
+            Some((last, count)) if *last == frame => *count += 1,
+            _ => deduped.push((frame, 1)),
+        }
+    }
+
+    println!("Stack trace (most recent call first):");
+
+    for (index, (frame, count)) in deduped.iter().take(depth).enumerate() {
+        let name = frame.name.as_deref().unwrap_or("<lambda>");
+        let call_site = match &frame.call_site {
+            Some(span) => format!(
+                " at {}:{}:{}",
+                span.file
+                    .as_ref()
+                    .map_or(filename.to_string(), |file| file.display().to_string()),
+                span.start.line,
+                span.start.col
+            ),
+            None => String::new(),
+        };
+        let repeated = match count {
+            1 => String::new(),
+            count => format!(" (repeated {} times)", count),
+        };
+
+        println!("  {}: {}{}{}", index, name, call_site, repeated);
+    }
+
+    if deduped.len() > depth {
+        let hidden: usize = deduped[depth..].iter().map(|(_, count)| count).sum();
+
+        println!("  ... and {} more", hidden);
+    }
 }
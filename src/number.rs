@@ -0,0 +1,283 @@
+use super::LanaErr;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// A Lana number. Numbers form a tower: integers are exact and grow into big integers instead of
+/// overflowing, dividing integers produces exact ratios, and floats are contagious, so operating on
+/// a float and any other number produces a float.
+#[derive(Clone, Debug)]
+pub enum LanaNumber {
+    Int(i64),
+    /// Only holds integers that don't fit an `i64`.
+    BigInt(Rc<BigInt>),
+    /// Never has a denominator of 1.
+    Ratio(Rc<BigRational>),
+    Float(f64),
+}
+
+/// Two numbers converted to the same level of the tower.
+enum Pair {
+    Ints(i64, i64),
+    BigInts(BigInt, BigInt),
+    Ratios(BigRational, BigRational),
+    Floats(f64, f64),
+}
+
+impl LanaNumber {
+    /// Reads a number literal, like `42`, `-1.5`, `1/3`, `0x1F`, `0b101` or `1_000_000`.
+    pub fn parse(text: &str) -> Option<LanaNumber> {
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+
+        let mut chars = unsigned.chars();
+        let looks_like_a_number = match chars.next() {
+            Some('.') => chars.next().is_some_and(|c| c.is_ascii_digit()),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        };
+        if !looks_like_a_number {
+            return None;
+        }
+
+        let digits = unsigned.replace('_', "");
+        let number = if let Some(hex) = digits.strip_prefix("0x") {
+            parse_integer(hex, 16)?
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            parse_integer(binary, 2)?
+        } else if let Some(octal) = digits.strip_prefix("0o") {
+            parse_integer(octal, 8)?
+        } else if let Some((numerator, denominator)) = digits.split_once('/') {
+            let numerator = parse_integer(numerator, 10)?.to_bigint()?;
+            let denominator = parse_integer(denominator, 10)?.to_bigint()?;
+            if denominator.is_zero() {
+                return None;
+            }
+
+            BigRational::new(numerator, denominator).into()
+        } else if digits.chars().all(|c| c.is_ascii_digit()) {
+            parse_integer(&digits, 10)?
+        } else {
+            LanaNumber::Float(digits.parse().ok()?)
+        };
+
+        Some(if negative { number.neg() } else { number })
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            LanaNumber::Int(n) => *n as f64,
+            LanaNumber::BigInt(n) => n.to_f64().unwrap_or(f64::NAN),
+            LanaNumber::Ratio(n) => n.to_f64().unwrap_or(f64::NAN),
+            LanaNumber::Float(n) => *n,
+        }
+    }
+
+    /// The number as an `i64`, if it is an integer that fits one.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            LanaNumber::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number as a big integer, if it is an integer.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            LanaNumber::Int(n) => Some(BigInt::from(*n)),
+            LanaNumber::BigInt(n) => Some((**n).clone()),
+            _ => None,
+        }
+    }
+
+    fn to_ratio(&self) -> Option<BigRational> {
+        match self {
+            LanaNumber::Ratio(n) => Some((**n).clone()),
+            other => other.to_bigint().map(BigRational::from_integer),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            LanaNumber::Int(n) => *n == 0,
+            LanaNumber::Float(n) => *n == 0.0,
+            // Zero always fits an `i64`, and ratios are never whole numbers
+            LanaNumber::BigInt(_) | LanaNumber::Ratio(_) => false,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, LanaNumber::Int(_) | LanaNumber::BigInt(_))
+    }
+
+    fn level(&self) -> u8 {
+        match self {
+            LanaNumber::Int(_) => 0,
+            LanaNumber::BigInt(_) => 1,
+            LanaNumber::Ratio(_) => 2,
+            LanaNumber::Float(_) => 3,
+        }
+    }
+
+    fn coerce(&self, other: &LanaNumber) -> Pair {
+        const CONVERTED: &str = "numbers are only converted up the tower";
+
+        match (self, other, self.level().max(other.level())) {
+            (LanaNumber::Int(a), LanaNumber::Int(b), _) => Pair::Ints(*a, *b),
+            (_, _, 1) => Pair::BigInts(
+                self.to_bigint().expect(CONVERTED),
+                other.to_bigint().expect(CONVERTED),
+            ),
+            (_, _, 2) => Pair::Ratios(
+                self.to_ratio().expect(CONVERTED),
+                other.to_ratio().expect(CONVERTED),
+            ),
+            _ => Pair::Floats(self.to_f64(), other.to_f64()),
+        }
+    }
+
+    pub fn add(&self, other: &LanaNumber) -> LanaNumber {
+        match self.coerce(other) {
+            Pair::Ints(a, b) => a
+                .checked_add(b)
+                .map(LanaNumber::Int)
+                .unwrap_or_else(|| (BigInt::from(a) + b).into()),
+            Pair::BigInts(a, b) => (a + b).into(),
+            Pair::Ratios(a, b) => (a + b).into(),
+            Pair::Floats(a, b) => LanaNumber::Float(a + b),
+        }
+    }
+
+    pub fn sub(&self, other: &LanaNumber) -> LanaNumber {
+        match self.coerce(other) {
+            Pair::Ints(a, b) => a
+                .checked_sub(b)
+                .map(LanaNumber::Int)
+                .unwrap_or_else(|| (BigInt::from(a) - b).into()),
+            Pair::BigInts(a, b) => (a - b).into(),
+            Pair::Ratios(a, b) => (a - b).into(),
+            Pair::Floats(a, b) => LanaNumber::Float(a - b),
+        }
+    }
+
+    pub fn mul(&self, other: &LanaNumber) -> LanaNumber {
+        match self.coerce(other) {
+            Pair::Ints(a, b) => a
+                .checked_mul(b)
+                .map(LanaNumber::Int)
+                .unwrap_or_else(|| (BigInt::from(a) * b).into()),
+            Pair::BigInts(a, b) => (a * b).into(),
+            Pair::Ratios(a, b) => (a * b).into(),
+            Pair::Floats(a, b) => LanaNumber::Float(a * b),
+        }
+    }
+
+    /// Divides exactly, unless one of the numbers is a float. Dividing integers produces a ratio
+    /// when they don't divide evenly.
+    pub fn div(&self, other: &LanaNumber) -> Result<LanaNumber, LanaErr> {
+        match self.coerce(other) {
+            Pair::Floats(a, b) => Ok(LanaNumber::Float(a / b)),
+            _ if other.is_zero() => Err(LanaErr::ArithmeticError("Divide by zero".into())),
+            Pair::Ints(a, b) if a.checked_rem(b) == Some(0) => Ok(LanaNumber::Int(a / b)),
+            Pair::Ints(a, b) => Ok(BigRational::new(a.into(), b.into()).into()),
+            Pair::BigInts(a, b) => Ok(BigRational::new(a, b).into()),
+            Pair::Ratios(a, b) => Ok((a / b).into()),
+        }
+    }
+
+    pub fn neg(&self) -> LanaNumber {
+        LanaNumber::Int(0).sub(self)
+    }
+
+    /// Compares numbers by value, regardless of their type. `None` if either is NaN.
+    pub fn compare(&self, other: &LanaNumber) -> Option<Ordering> {
+        match self.coerce(other) {
+            Pair::Ints(a, b) => Some(a.cmp(&b)),
+            Pair::BigInts(a, b) => Some(a.cmp(&b)),
+            Pair::Ratios(a, b) => Some(a.cmp(&b)),
+            Pair::Floats(a, b) => a.partial_cmp(&b),
+        }
+    }
+}
+
+fn parse_integer(digits: &str, radix: u32) -> Option<LanaNumber> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    BigInt::parse_bytes(digits.as_bytes(), radix).map(LanaNumber::from)
+}
+
+impl From<i64> for LanaNumber {
+    fn from(n: i64) -> Self {
+        LanaNumber::Int(n)
+    }
+}
+
+impl From<f64> for LanaNumber {
+    fn from(n: f64) -> Self {
+        LanaNumber::Float(n)
+    }
+}
+
+impl From<BigInt> for LanaNumber {
+    fn from(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => LanaNumber::Int(n),
+            None => LanaNumber::BigInt(Rc::new(n)),
+        }
+    }
+}
+
+impl From<BigRational> for LanaNumber {
+    fn from(n: BigRational) -> Self {
+        if n.is_integer() {
+            n.to_integer().into()
+        } else {
+            LanaNumber::Ratio(Rc::new(n))
+        }
+    }
+}
+
+/// Numbers are equal when they have the same type and value, so `1` and `1.0` are different map
+/// keys, and `=` agrees. Use `compare`, which `==` is built on, to compare them by value.
+impl PartialEq for LanaNumber {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LanaNumber::Int(a), LanaNumber::Int(b)) => a == b,
+            (LanaNumber::BigInt(a), LanaNumber::BigInt(b)) => a == b,
+            (LanaNumber::Ratio(a), LanaNumber::Ratio(b)) => a == b,
+            (LanaNumber::Float(a), LanaNumber::Float(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Hash for LanaNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            LanaNumber::Int(n) => n.hash(state),
+            LanaNumber::BigInt(n) => n.hash(state),
+            LanaNumber::Ratio(n) => n.hash(state),
+            // 0.0 and -0.0 are equal, so they must hash the same
+            LanaNumber::Float(n) => (if *n == 0.0 { 0.0 } else { *n }).to_bits().hash(state),
+        }
+    }
+}
+
+/// Floats are always printed with a decimal point or an exponent, so they don't look like integers.
+impl std::fmt::Display for LanaNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LanaNumber::Int(n) => write!(f, "{}", n),
+            LanaNumber::BigInt(n) => write!(f, "{}", n),
+            LanaNumber::Ratio(n) => write!(f, "{}", n),
+            LanaNumber::Float(n) => write!(f, "{:?}", n),
+        }
+    }
+}
@@ -1,20 +1,99 @@
-use super::LanaErr;
+use super::{LanaEnv, LanaErr, LanaError, LanaNumber, SrcSpan};
+use indexmap::{IndexMap, IndexSet};
+use regex::Regex;
+use std::cell::{OnceCell, RefCell};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 use std::rc::Rc;
 
 #[derive(Clone)]
+#[non_exhaustive]
 pub enum LanaExpr {
     Nil,
     Bool(bool),
     Keyword(String),
     Symbol(String),
     String(String),
-    Number(f64),
-    List(Vec<LanaExpr>),
+    Number(LanaNumber),
+    /// A regular expression, compiled when it is read.
+    Regex(Rc<LanaRegex>),
+    /// Lists read from source remember where they and their items came from.
+    List(Vec<LanaExpr>, Option<Rc<ListSpans>>),
+    Vector(Vec<LanaExpr>),
+    Map(IndexMap<LanaExpr, LanaExpr>),
+    Set(IndexSet<LanaExpr>),
     Func(fn(&[LanaExpr]) -> Result<LanaExpr, LanaErr>),
+    EnvFunc(fn(&[LanaExpr], &LanaEnv) -> Result<LanaExpr, LanaErr>),
+    Native(NativeFn),
     Lambda(LanaLambda),
+    Macro(LanaLambda),
+    Error(Rc<LanaError>),
+    Atom(Rc<LanaAtom>),
+    /// An instance of a type declared with `defrecord`.
+    Record(Rc<LanaRecord>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListSpans {
+    pub span: SrcSpan,
+    pub items: Vec<SrcSpan>,
 }
 
 impl LanaExpr {
+    pub fn span(&self) -> Option<&SrcSpan> {
+        match self {
+            LanaExpr::List(_, Some(spans)) => Some(&spans.span),
+            _ => None,
+        }
+    }
+
+    pub fn int(n: i64) -> Self {
+        LanaExpr::Number(LanaNumber::Int(n))
+    }
+
+    pub fn float(n: f64) -> Self {
+        LanaExpr::Number(LanaNumber::Float(n))
+    }
+
+    /// `false` and `nil` are the only falsey values.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, LanaExpr::Bool(false) | LanaExpr::Nil)
+    }
+
+    /// Orders values of the same type: numbers by value, strings, keywords and symbols
+    /// alphabetically, `false` before `true`, and lists and vectors element by element. Returns
+    /// `None` for values that can't be ordered, like values of different types or functions.
+    pub fn compare(&self, other: &LanaExpr) -> Option<Ordering> {
+        match (self, other) {
+            (LanaExpr::Nil, LanaExpr::Nil) => Some(Ordering::Equal),
+            (LanaExpr::Bool(a), LanaExpr::Bool(b)) => Some(a.cmp(b)),
+            // NaN is ordered after every other number, so sorting is still total
+            (LanaExpr::Number(a), LanaExpr::Number(b)) => Some(
+                a.compare(b)
+                    .unwrap_or_else(|| a.to_f64().total_cmp(&b.to_f64())),
+            ),
+            (LanaExpr::String(a), LanaExpr::String(b))
+            | (LanaExpr::Keyword(a), LanaExpr::Keyword(b))
+            | (LanaExpr::Symbol(a), LanaExpr::Symbol(b)) => Some(a.cmp(b)),
+            (
+                LanaExpr::List(a, _) | LanaExpr::Vector(a),
+                LanaExpr::List(b, _) | LanaExpr::Vector(b),
+            ) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y)? {
+                        Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+
+                Some(a.len().cmp(&b.len()))
+            }
+            _ => None,
+        }
+    }
+
     pub fn enum_name(&self) -> String {
         match self {
             LanaExpr::Bool(_b) => "boolean".into(),
@@ -22,9 +101,19 @@ impl LanaExpr {
             LanaExpr::String(_s) => "string".into(),
             LanaExpr::Keyword(_s) => "keyword".into(),
             LanaExpr::Number(_n) => "number".into(),
-            LanaExpr::List(_) => "list".into(),
+            LanaExpr::Regex(_) => "regex".into(),
+            LanaExpr::List(_, _) => "list".into(),
+            LanaExpr::Vector(_) => "vector".into(),
+            LanaExpr::Map(_) => "map".into(),
+            LanaExpr::Set(_) => "set".into(),
             LanaExpr::Func(_) => "function".into(),
+            LanaExpr::EnvFunc(_) => "function".into(),
+            LanaExpr::Native(_) => "function".into(),
             LanaExpr::Lambda(_) => "lambda".into(),
+            LanaExpr::Macro(_) => "macro".into(),
+            LanaExpr::Error(_) => "error".into(),
+            LanaExpr::Atom(_) => "atom".into(),
+            LanaExpr::Record(record) => record.record_type.name.clone(),
             LanaExpr::Nil => "nil".into(),
         }
     }
@@ -39,7 +128,8 @@ impl LanaExpr {
             LanaExpr::Keyword(_) => self.to_string().bold().yellow().to_string(),
             LanaExpr::String(_) => self.to_string().bold().green().to_string(),
             LanaExpr::Number(_) => self.to_string().bold().cyan().to_string(),
-            LanaExpr::List(list) => {
+            LanaExpr::Regex(_) => self.to_string().bold().red().to_string(),
+            LanaExpr::List(list, _) => {
                 let xs: Vec<String> = list
                     .iter()
                     .map(|value| value.to_colorized_string())
@@ -47,8 +137,53 @@ impl LanaExpr {
 
                 format!("({})", xs.join(", "))
             }
+            LanaExpr::Vector(vector) => {
+                let xs: Vec<String> = vector
+                    .iter()
+                    .map(|value| value.to_colorized_string())
+                    .collect();
+
+                format!("[{}]", xs.join(", "))
+            }
+            LanaExpr::Map(map) => {
+                let xs: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{} {}",
+                            key.to_colorized_string(),
+                            value.to_colorized_string()
+                        )
+                    })
+                    .collect();
+
+                format!("{{{}}}", xs.join(", "))
+            }
+            LanaExpr::Set(set) => {
+                let xs: Vec<String> = set
+                    .iter()
+                    .map(|value| value.to_colorized_string())
+                    .collect();
+
+                format!("#{{{}}}", xs.join(", "))
+            }
             LanaExpr::Func(_) => self.to_string().green().to_string(),
+            LanaExpr::EnvFunc(_) => self.to_string().green().to_string(),
+            LanaExpr::Native(_) => self.to_string().green().to_string(),
             LanaExpr::Lambda(_) => self.to_string().green().to_string(),
+            LanaExpr::Macro(_) => self.to_string().green().to_string(),
+            LanaExpr::Error(_) => self.to_string().red().to_string(),
+            LanaExpr::Atom(atom) => format!("#atom[{}]", atom.value.borrow().to_colorized_string()),
+            LanaExpr::Record(record) => {
+                let xs: Vec<String> = record
+                    .fields()
+                    .map(|(field, value)| {
+                        format!("{} {}", field.bold().yellow(), value.to_colorized_string())
+                    })
+                    .collect();
+
+                format!("#{}{{{}}}", record.record_type.name, xs.join(" "))
+            }
         }
     }
 }
@@ -62,13 +197,48 @@ impl std::fmt::Display for LanaExpr {
             LanaExpr::String(s) => s.clone(),
             LanaExpr::Keyword(s) => s.clone(),
             LanaExpr::Number(n) => n.to_string(),
+            LanaExpr::Regex(regex) => format!("#\"{}\"", regex.as_str()),
             LanaExpr::Func(function) => format!("fn({})", *function as usize),
+            LanaExpr::EnvFunc(function) => format!("fn({})", *function as usize),
+            LanaExpr::Native(function) => format!("fn({})", function.name),
             LanaExpr::Lambda(lambda) => format!("lambda({:p})", lambda),
-            LanaExpr::List(list) => {
+            LanaExpr::Macro(lambda) => format!("macro({:p})", lambda),
+            LanaExpr::Error(error) => format!(
+                "#error{{:kind {}, :message {:?}, :data {}}}",
+                error.kind, error.message, error.data
+            ),
+            LanaExpr::Atom(atom) => format!("#atom[{}]", atom.value.borrow()),
+            LanaExpr::Record(record) => {
+                let xs: Vec<String> = record
+                    .fields()
+                    .map(|(field, value)| format!("{} {}", field, value))
+                    .collect();
+
+                format!("#{}{{{}}}", record.record_type.name, xs.join(" "))
+            }
+            LanaExpr::List(list, _) => {
                 let xs: Vec<String> = list.iter().map(|value| value.to_string()).collect();
 
                 format!("({})", xs.join(", "))
             }
+            LanaExpr::Vector(vector) => {
+                let xs: Vec<String> = vector.iter().map(|value| value.to_string()).collect();
+
+                format!("[{}]", xs.join(", "))
+            }
+            LanaExpr::Map(map) => {
+                let xs: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| format!("{} {}", key, value))
+                    .collect();
+
+                format!("{{{}}}", xs.join(", "))
+            }
+            LanaExpr::Set(set) => {
+                let xs: Vec<String> = set.iter().map(|value| value.to_string()).collect();
+
+                format!("#{{{}}}", xs.join(", "))
+            }
         };
 
         write!(f, "{}", string)
@@ -78,25 +248,262 @@ impl std::fmt::Display for LanaExpr {
 impl std::fmt::Debug for LanaExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            LanaExpr::Nil => write!(f, "{}", self.to_string()),
-            LanaExpr::String(_) => write!(f, "{} \"{}\"", self.enum_name(), self.to_string()),
-            _ => write!(f, "{} '{}'", self.enum_name(), self.to_string()),
+            LanaExpr::Nil => write!(f, "{}", self),
+            LanaExpr::String(_) => write!(f, "{} \"{}\"", self.enum_name(), self),
+            _ => write!(f, "{} '{}'", self.enum_name(), self),
         }
     }
 }
 
+/// Values are compared structurally. Lists and vectors with the same elements are equal, like in
+/// Clojure, regexes are equal when they have the same pattern, while functions and atoms are only
+/// equal to themselves.
 impl PartialEq for LanaExpr {
     fn eq(&self, other: &Self) -> bool {
-        if self.enum_name() != other.enum_name() {
-            return false;
+        match (self, other) {
+            (LanaExpr::Nil, LanaExpr::Nil) => true,
+            (LanaExpr::Bool(a), LanaExpr::Bool(b)) => a == b,
+            (LanaExpr::Keyword(a), LanaExpr::Keyword(b)) => a == b,
+            (LanaExpr::Symbol(a), LanaExpr::Symbol(b)) => a == b,
+            (LanaExpr::String(a), LanaExpr::String(b)) => a == b,
+            (LanaExpr::Number(a), LanaExpr::Number(b)) => a == b,
+            (LanaExpr::Regex(a), LanaExpr::Regex(b)) => a.as_str() == b.as_str(),
+            (
+                LanaExpr::List(a, _) | LanaExpr::Vector(a),
+                LanaExpr::List(b, _) | LanaExpr::Vector(b),
+            ) => a == b,
+            (LanaExpr::Map(a), LanaExpr::Map(b)) => a == b,
+            (LanaExpr::Set(a), LanaExpr::Set(b)) => a == b,
+            (LanaExpr::Func(a), LanaExpr::Func(b)) => *a as usize == *b as usize,
+            (LanaExpr::EnvFunc(a), LanaExpr::EnvFunc(b)) => *a as usize == *b as usize,
+            (LanaExpr::Native(a), LanaExpr::Native(b)) => a == b,
+            (LanaExpr::Lambda(a), LanaExpr::Lambda(b)) => a == b,
+            (LanaExpr::Macro(a), LanaExpr::Macro(b)) => a == b,
+            (LanaExpr::Error(a), LanaExpr::Error(b)) => a == b,
+            (LanaExpr::Atom(a), LanaExpr::Atom(b)) => Rc::ptr_eq(a, b),
+            (LanaExpr::Record(a), LanaExpr::Record(b)) => {
+                Rc::ptr_eq(&a.record_type, &b.record_type) && a.values == b.values
+            }
+            _ => false,
         }
+    }
+}
+
+impl Eq for LanaExpr {}
 
-        self.to_string() == other.to_string()
+impl Hash for LanaExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            LanaExpr::Nil => {}
+            LanaExpr::Bool(b) => b.hash(state),
+            LanaExpr::Keyword(s) | LanaExpr::Symbol(s) | LanaExpr::String(s) => s.hash(state),
+            LanaExpr::Number(n) => n.hash(state),
+            LanaExpr::Regex(regex) => regex.as_str().hash(state),
+            LanaExpr::List(xs, _) | LanaExpr::Vector(xs) => xs.hash(state),
+            LanaExpr::Map(map) => unordered_hash(map.iter()).hash(state),
+            LanaExpr::Set(set) => unordered_hash(set.iter()).hash(state),
+            LanaExpr::Func(function) => (*function as usize).hash(state),
+            LanaExpr::EnvFunc(function) => (*function as usize).hash(state),
+            LanaExpr::Native(function) => {
+                (Rc::as_ptr(&function.body) as *const () as usize).hash(state)
+            }
+            LanaExpr::Lambda(lambda) | LanaExpr::Macro(lambda) => lambda.name.hash(state),
+            LanaExpr::Error(error) => error.message.hash(state),
+            LanaExpr::Atom(atom) => (Rc::as_ptr(atom) as usize).hash(state),
+            LanaExpr::Record(record) => {
+                record.record_type.name.hash(state);
+                record.values.hash(state);
+            }
+        }
+    }
+}
+
+/// Maps and sets are equal regardless of insertion order, so their hash can't depend on it either.
+fn unordered_hash<T: Hash>(items: impl Iterator<Item = T>) -> u64 {
+    items
+        .map(|item| {
+            let mut hasher = DefaultHasher::new();
+            item.hash(&mut hasher);
+
+            hasher.finish()
+        })
+        .fold(0, u64::wrapping_add)
+}
+
+/// A regex, compiled when it is read or created with `re-pattern`.
+#[derive(Debug)]
+pub struct LanaRegex {
+    regex: Regex,
+    /// The regex anchored at both ends, for `re-matches`, compiled the first time it is needed.
+    anchored: OnceCell<Regex>,
+}
+
+impl LanaRegex {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(LanaRegex {
+            regex: Regex::new(pattern)?,
+            anchored: OnceCell::new(),
+        })
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Matches only the whole input. The leftmost match isn't always the longest one, so checking
+    /// the span of `regex`'s match isn't enough.
+    pub fn anchored(&self) -> &Regex {
+        self.anchored.get_or_init(|| {
+            Regex::new(&format!("^(?:{})$", self.regex.as_str()))
+                .expect("anchoring a valid regex keeps it valid")
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+/// A mutable reference, created with `atom`. Copies of an atom share its value.
+pub struct LanaAtom {
+    pub value: RefCell<LanaExpr>,
+    /// Functions called with their key, the atom, and its old and new values when it changes.
+    pub watchers: RefCell<IndexMap<LanaExpr, LanaExpr>>,
+}
+
+impl LanaAtom {
+    pub fn new(value: LanaExpr) -> Self {
+        LanaAtom {
+            value: RefCell::new(value),
+            watchers: RefCell::new(IndexMap::new()),
+        }
+    }
+}
+
+/// A type declared with `defrecord`.
+#[derive(Debug)]
+pub struct LanaRecordType {
+    pub name: String,
+    /// The names of its fields, as keywords.
+    pub fields: Vec<String>,
+}
+
+/// A record, with a value for each field of its type, in the same order.
+pub struct LanaRecord {
+    pub record_type: Rc<LanaRecordType>,
+    pub values: Vec<LanaExpr>,
+}
+
+impl LanaRecord {
+    /// The value of the field named by the keyword `field`.
+    pub fn get(&self, field: &LanaExpr) -> Option<&LanaExpr> {
+        let field = match field {
+            LanaExpr::Keyword(field) => field,
+            _ => return None,
+        };
+
+        self.fields()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&String, &LanaExpr)> {
+        self.record_type.fields.iter().zip(&self.values)
     }
 }
 
 #[derive(Clone, PartialEq)]
 pub struct LanaLambda {
-    pub params: Rc<LanaExpr>,
+    pub name: Option<String>,
+    pub arities: Rc<Vec<LanaArity>>,
+    pub env: LanaEnv,
+    /// The file it was defined in, so errors raised in its body can point there.
+    pub file: Option<Rc<Path>>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct LanaArity {
+    pub params: Rc<Vec<LanaExpr>>,
     pub body: Rc<LanaExpr>,
 }
+
+type NativeBody = dyn Fn(&[LanaExpr], &LanaEnv) -> Result<LanaExpr, LanaErr>;
+
+/// A function implemented in Rust. Unlike `Func`, it may close over state, like a database handle
+/// or a counter, and it knows its name, how many arguments it takes and what it does.
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    pub min_args: usize,
+    /// `None` for functions taking any number of arguments.
+    pub max_args: Option<usize>,
+    pub doc: Option<String>,
+    body: Rc<NativeBody>,
+}
+
+impl NativeFn {
+    pub fn new(
+        name: &str,
+        body: impl Fn(&[LanaExpr]) -> Result<LanaExpr, LanaErr> + 'static,
+    ) -> Self {
+        Self::with_env(name, move |args, _env| body(args))
+    }
+
+    /// Like `new`, for builtins that call back into Lana code.
+    pub(crate) fn with_env(
+        name: &str,
+        body: impl Fn(&[LanaExpr], &LanaEnv) -> Result<LanaExpr, LanaErr> + 'static,
+    ) -> Self {
+        NativeFn {
+            name: name.into(),
+            min_args: 0,
+            max_args: None,
+            doc: None,
+            body: Rc::new(body),
+        }
+    }
+
+    /// Sets how many arguments the function takes. They are checked before it is called.
+    pub fn arity(self, min_args: usize, max_args: Option<usize>) -> Self {
+        NativeFn {
+            min_args,
+            max_args,
+            ..self
+        }
+    }
+
+    pub fn doc(self, doc: &str) -> Self {
+        NativeFn {
+            doc: Some(doc.into()),
+            ..self
+        }
+    }
+
+    pub fn call(&self, args: &[LanaExpr], env: &LanaEnv) -> Result<LanaExpr, LanaErr> {
+        let too_many = self.max_args.is_some_and(|max| args.len() > max);
+
+        if args.len() < self.min_args || too_many {
+            let expected = match self.max_args {
+                Some(max) if max == self.min_args => max.to_string(),
+                Some(max) => format!("{} to {}", self.min_args, max),
+                None => format!("at least {}", self.min_args),
+            };
+
+            return Err(LanaErr::ArityError(format!(
+                "'{}' expected {} argument(s), got {}",
+                self.name,
+                expected,
+                args.len()
+            )));
+        }
+
+        (self.body)(args, env)
+    }
+}
+
+/// Native functions are only equal to themselves.
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body)
+    }
+}
@@ -1,28 +1,37 @@
 mod lana_expr;
 
-use super::{LanaErr, Token, TokenKind};
-pub use lana_expr::{LanaExpr, LanaLambda};
+use super::{LanaEnv, LanaErr, LanaError, LanaNumber, SrcSpan, StringPart, Token, TokenKind};
+use indexmap::{IndexMap, IndexSet};
+pub use lana_expr::{
+    LanaArity, LanaAtom, LanaExpr, LanaLambda, LanaRecord, LanaRecordType, LanaRegex, ListSpans,
+    NativeFn,
+};
+use std::rc::Rc;
 
+#[cfg(test)]
 pub fn parse(tokens: &[Token]) -> Result<(LanaExpr, &[Token]), LanaErr> {
-    let (token, rest) = tokens
-        .split_first()
-        .ok_or_else(|| LanaErr::Reason("Could not get token".into()))?;
+    let (expr, _, rest) = parse_spanned(tokens)?;
 
-    match token.kind {
-        TokenKind::LParen => read_seq(rest, token.clone()),
-        TokenKind::RParen => Err(LanaErr::UnexpectedToken(token.clone())),
-        _ => Ok((parse_atom(token)?, rest)),
-    }
+    Ok((expr, rest))
 }
 
+#[cfg(test)]
 pub fn parse_all(tokens: &[Token]) -> Result<Vec<LanaExpr>, LanaErr> {
+    Ok(parse_all_spanned(tokens)?
+        .into_iter()
+        .map(|(expr, _)| expr)
+        .collect())
+}
+
+/// Like `parse_all`, but also returns where each top-level form was read from.
+pub fn parse_all_spanned(tokens: &[Token]) -> Result<Vec<(LanaExpr, SrcSpan)>, LanaErr> {
     let mut exprs = vec![];
     let mut input = tokens;
 
     while !input.is_empty() {
-        let (expr, rest) = parse(input)?;
+        let (expr, span, rest) = parse_spanned(input)?;
 
-        exprs.push(expr);
+        exprs.push((expr, span));
 
         input = rest;
     }
@@ -30,30 +39,215 @@ pub fn parse_all(tokens: &[Token]) -> Result<Vec<LanaExpr>, LanaErr> {
     Ok(exprs)
 }
 
-fn read_seq(tokens: &[Token], opening_token: Token) -> Result<(LanaExpr, &[Token]), LanaErr> {
+fn parse_spanned(tokens: &[Token]) -> Result<(LanaExpr, SrcSpan, &[Token]), LanaErr> {
+    let (token, rest) = tokens
+        .split_first()
+        .ok_or_else(|| LanaErr::Reason("Could not get token".into()))?;
+
+    match token.kind {
+        TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace | TokenKind::HashLBrace => {
+            read_seq(rest, token.clone())
+        }
+        TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => {
+            Err(LanaErr::UnexpectedToken(token.clone()))
+        }
+        TokenKind::Quote => read_quoted("quote", rest, token),
+        TokenKind::Quasiquote => read_quoted("quasiquote", rest, token),
+        TokenKind::Unquote => read_quoted("unquote", rest, token),
+        TokenKind::SpliceUnquote => read_quoted("splice-unquote", rest, token),
+        // Qualified, so `@x` derefs even where `deref` is shadowed
+        TokenKind::Deref => read_quoted("lana.core/deref", rest, token),
+        TokenKind::InterpolatedString(ref parts) => {
+            Ok((read_interpolated(parts, token)?, token.span(), rest))
+        }
+        _ => Ok((parse_atom(token)?, token.span(), rest)),
+    }
+}
+
+fn read_seq(
+    tokens: &[Token],
+    opening_token: Token,
+) -> Result<(LanaExpr, SrcSpan, &[Token]), LanaErr> {
+    let (closing_kind, closing_char) = match opening_token.kind {
+        TokenKind::LParen => (TokenKind::RParen, ')'),
+        TokenKind::LBracket => (TokenKind::RBracket, ']'),
+        _ => (TokenKind::RBrace, '}'),
+    };
+
     let mut res: Vec<LanaExpr> = vec![];
+    let mut spans: Vec<SrcSpan> = vec![];
     let mut xs = tokens;
 
     loop {
         let (next_token, rest) = xs
             .split_first()
-            .ok_or_else(|| LanaErr::UnterminatedExpr((')', opening_token.clone())))?;
+            .ok_or_else(|| LanaErr::UnterminatedExpr((closing_char, opening_token.clone())))?;
 
-        if next_token.kind == TokenKind::RParen {
-            return Ok((LanaExpr::List(res), rest));
+        if next_token.kind == closing_kind {
+            let span = SrcSpan::new(opening_token.start.clone(), next_token.loc.clone());
+            let seq = build_seq(&opening_token, res, span.clone(), spans)?;
+
+            return Ok((seq, span, rest));
         }
 
-        let (expr, new_xs) = parse(&xs)?;
+        let (expr, expr_span, new_xs) = parse_spanned(xs)?;
 
         res.push(expr);
+        spans.push(expr_span);
         xs = new_xs;
     }
 }
 
+fn build_seq(
+    opening_token: &Token,
+    items: Vec<LanaExpr>,
+    span: SrcSpan,
+    item_spans: Vec<SrcSpan>,
+) -> Result<LanaExpr, LanaErr> {
+    match opening_token.kind {
+        TokenKind::LBracket => Ok(LanaExpr::Vector(items)),
+        TokenKind::HashLBrace => {
+            let mut set = IndexSet::new();
+
+            for (item, item_span) in items.into_iter().zip(&item_spans) {
+                if set.contains(&item) {
+                    return Err(duplicate_error("Set", &item, opening_token, item_span));
+                }
+                set.insert(item);
+            }
+
+            Ok(LanaExpr::Set(set))
+        }
+        TokenKind::LBrace => {
+            if !items.len().is_multiple_of(2) {
+                return Err(LanaErr::Reason(format!(
+                    "Map literal at {} must contain an even number of forms",
+                    opening_token.loc
+                )));
+            }
+
+            let mut map = IndexMap::new();
+            let mut items = items.into_iter();
+            let mut key_spans = item_spans.iter().step_by(2);
+
+            while let (Some(key), Some(value), Some(key_span)) =
+                (items.next(), items.next(), key_spans.next())
+            {
+                if map.contains_key(&key) {
+                    return Err(duplicate_error("Map", &key, opening_token, key_span));
+                }
+                map.insert(key, value);
+            }
+
+            Ok(LanaExpr::Map(map))
+        }
+        _ => Ok(LanaExpr::List(
+            items,
+            Some(Rc::new(ListSpans {
+                span,
+                items: item_spans,
+            })),
+        )),
+    }
+}
+
+/// Literals can't repeat a key or member, as all but one of them would be silently dropped.
+fn duplicate_error(
+    literal: &str,
+    duplicate: &LanaExpr,
+    opening_token: &Token,
+    span: &SrcSpan,
+) -> LanaErr {
+    LanaErr::Reason(format!(
+        "{} literal at {} contains {:?} more than once",
+        literal, opening_token.loc, duplicate
+    ))
+    .located(span)
+}
+
+/// Expands reader shorthands like `'x` into their long form, `(quote x)`.
+fn read_quoted<'a>(
+    form_name: &str,
+    tokens: &'a [Token],
+    quote_token: &Token,
+) -> Result<(LanaExpr, SrcSpan, &'a [Token]), LanaErr> {
+    if tokens.is_empty() {
+        return Err(LanaErr::UnexpectedToken(quote_token.clone()));
+    }
+
+    let (expr, expr_span, rest) = parse_spanned(tokens)?;
+    let span = SrcSpan::new(quote_token.start.clone(), expr_span.end.clone());
+    let spans = ListSpans {
+        span: span.clone(),
+        items: vec![quote_token.span(), expr_span],
+    };
+
+    Ok((
+        LanaExpr::List(
+            vec![LanaExpr::Symbol(form_name.into()), expr],
+            Some(Rc::new(spans)),
+        ),
+        span,
+        rest,
+    ))
+}
+
+/// Expands an interpolated string into a call to `str`, so `$"x is #{x}"` is read as
+/// `(lana.core/str "x is " x)`. The qualified name refers to the builtin even where `str` is
+/// shadowed.
+fn read_interpolated(parts: &[StringPart], token: &Token) -> Result<LanaExpr, LanaErr> {
+    let mut items = vec![LanaExpr::Symbol("lana.core/str".into())];
+    let mut spans = vec![token.span()];
+
+    for part in parts {
+        match part {
+            StringPart::Text(text) => {
+                items.push(LanaExpr::String(text.clone()));
+                spans.push(token.span());
+            }
+            StringPart::Code(tokens, span) => {
+                let mut forms = parse_all_spanned(tokens)?.into_iter();
+
+                match (forms.next(), forms.next()) {
+                    (Some((expr, expr_span)), None) => {
+                        items.push(expr);
+                        spans.push(expr_span);
+                    }
+                    (None, _) => {
+                        return Err(LanaErr::Reason(
+                            "Expected a form inside '#{}', got nothing".into(),
+                        )
+                        .located(span))
+                    }
+                    (Some(_), Some((_, extra_span))) => {
+                        return Err(
+                            LanaErr::Reason("Expected a single form inside '#{}'".into())
+                                .located(&extra_span),
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(LanaExpr::List(
+        items,
+        Some(Rc::new(ListSpans {
+            span: token.span(),
+            items: spans,
+        })),
+    ))
+}
+
 fn parse_atom(token: &Token) -> Result<LanaExpr, LanaErr> {
     match &token.kind {
-        TokenKind::Number(n) => Ok(LanaExpr::Number(*n)),
+        TokenKind::Number(n) => Ok(LanaExpr::Number(n.clone())),
         TokenKind::String(s) => Ok(LanaExpr::String(s.clone())),
+        TokenKind::Regex(pattern) => LanaRegex::new(pattern)
+            .map(|regex| LanaExpr::Regex(Rc::new(regex)))
+            .map_err(|err| {
+                LanaErr::Reason(format!("Invalid regex: {}", err)).located(&token.span())
+            }),
         TokenKind::Id(value) if value == "true" => Ok(LanaExpr::Bool(true)),
         TokenKind::Id(value) if value == "false" => Ok(LanaExpr::Bool(false)),
         TokenKind::Id(value) if value == "nil" => Ok(LanaExpr::Nil),
@@ -144,13 +338,19 @@ mod tests {
     #[test]
     fn it_parses_numbers() {
         let input = vec![
-            Token::new(TokenKind::Number(1.0), SrcLocation::new(1, 3)),
-            Token::new(TokenKind::Number(-1.0), SrcLocation::new(2, 3)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 3),
+            ),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(-1)),
+                SrcLocation::new(2, 3),
+            ),
         ];
 
         let tokens = parse_all(&input).expect("Could not parse number");
 
-        assert_eq!(vec![LanaExpr::Number(1.0), LanaExpr::Number(-1.0)], tokens);
+        assert_eq!(vec![LanaExpr::int(1), LanaExpr::int(-1)], tokens);
     }
 
     #[test]
@@ -174,20 +374,174 @@ mod tests {
 
         let result = parse_all(&input).expect("Could not parse empty list");
 
-        assert_eq!(vec![LanaExpr::List(vec![])], result);
+        assert_eq!(vec![LanaExpr::List(vec![], None)], result);
     }
 
     #[test]
     fn it_parses_a_list() {
         let input = vec![
             Token::new(TokenKind::LParen, SrcLocation::new(1, 1)),
-            Token::new(TokenKind::Number(1.0), SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 2),
+            ),
             Token::new(TokenKind::RParen, SrcLocation::new(1, 3)),
         ];
 
         let result = parse_all(&input).expect("Could not parse a list");
 
-        assert_eq!(vec![LanaExpr::List(vec![LanaExpr::Number(1.0)])], result);
+        assert_eq!(vec![LanaExpr::List(vec![LanaExpr::int(1)], None)], result);
+    }
+
+    #[test]
+    fn it_parses_a_vector() {
+        let input = vec![
+            Token::new(TokenKind::LBracket, SrcLocation::new(1, 1)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 2),
+            ),
+            Token::new(TokenKind::RBracket, SrcLocation::new(1, 3)),
+        ];
+
+        let result = parse_all(&input).expect("Could not parse a vector");
+
+        assert_eq!(vec![LanaExpr::Vector(vec![LanaExpr::int(1)])], result);
+    }
+
+    #[test]
+    fn it_parses_a_map() {
+        let input = vec![
+            Token::new(TokenKind::LBrace, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::Id(":a".into()), SrcLocation::new(1, 3)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 5),
+            ),
+            Token::new(TokenKind::RBrace, SrcLocation::new(1, 6)),
+        ];
+
+        let result = parse_all(&input).expect("Could not parse a map");
+
+        let mut map = IndexMap::new();
+        map.insert(LanaExpr::Keyword(":a".into()), LanaExpr::int(1));
+        assert_eq!(vec![LanaExpr::Map(map)], result);
+    }
+
+    #[test]
+    fn it_parses_a_set() {
+        let input = vec![
+            Token::new(TokenKind::HashLBrace, SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 3),
+            ),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(2)),
+                SrcLocation::new(1, 5),
+            ),
+            Token::new(TokenKind::RBrace, SrcLocation::new(1, 6)),
+        ];
+
+        let result = parse_all(&input).expect("Could not parse a set");
+
+        assert_eq!(
+            vec![LanaExpr::Set(
+                vec![LanaExpr::int(1), LanaExpr::int(2)]
+                    .into_iter()
+                    .collect()
+            )],
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_duplicate_set_members() {
+        let input = vec![
+            Token::new(TokenKind::HashLBrace, SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 3),
+            ),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 5),
+            ),
+            Token::new(TokenKind::RBrace, SrcLocation::new(1, 6)),
+        ];
+
+        let result = parse_all(&input).expect_err("Didn't fail on a duplicate member");
+
+        assert_eq!(
+            Some(SrcSpan::new(SrcLocation::new(1, 5), SrcLocation::new(1, 5))),
+            result.span()
+        );
+        assert_eq!(
+            LanaErr::Reason(
+                "Set literal at line 1, column 2 contains number '1' more than once".into()
+            ),
+            result.without_context()
+        );
+    }
+
+    #[test]
+    fn it_errors_on_duplicate_map_keys() {
+        let input = vec![
+            Token::new(TokenKind::LBrace, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::Id(":a".into()), SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 5),
+            ),
+            Token::new(TokenKind::Id(":a".into()), SrcLocation::new(1, 7)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(2)),
+                SrcLocation::new(1, 10),
+            ),
+            Token::new(TokenKind::RBrace, SrcLocation::new(1, 11)),
+        ];
+
+        let result = parse_all(&input).expect_err("Didn't fail on a duplicate key");
+
+        assert_eq!(
+            LanaErr::Reason(
+                "Map literal at line 1, column 1 contains keyword ':a' more than once".into()
+            ),
+            result.without_context()
+        );
+    }
+
+    #[test]
+    fn it_errors_on_maps_with_odd_number_of_forms() {
+        let input = vec![
+            Token::new(TokenKind::LBrace, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::Id(":a".into()), SrcLocation::new(1, 3)),
+            Token::new(TokenKind::RBrace, SrcLocation::new(1, 4)),
+        ];
+
+        let result = parse_all(&input).expect_err("Didn't fail on odd map");
+
+        assert_eq!(
+            LanaErr::Reason(
+                "Map literal at line 1, column 1 must contain an even number of forms".into()
+            ),
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_mismatched_delimiters() {
+        let input = vec![
+            Token::new(TokenKind::LBracket, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::RParen, SrcLocation::new(1, 2)),
+        ];
+
+        let result = parse_all(&input).expect_err("Didn't fail on mismatched delimiters");
+
+        assert_eq!(
+            LanaErr::UnexpectedToken(Token::new(TokenKind::RParen, SrcLocation::new(1, 2))),
+            result
+        );
     }
 
     #[test]
@@ -195,7 +549,10 @@ mod tests {
         let opening_paren = Token::new(TokenKind::LParen, SrcLocation::new(1, 1));
         let input = vec![
             opening_paren.clone(),
-            Token::new(TokenKind::Number(1.0), SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(1)),
+                SrcLocation::new(1, 2),
+            ),
         ];
 
         let result = parse_all(&input).expect_err("Didn't fail on unterminated list");
@@ -231,6 +588,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_parses_quote_shorthands() {
+        let input = vec![
+            Token::new(TokenKind::Quasiquote, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::LParen, SrcLocation::new(1, 2)),
+            Token::new(TokenKind::Unquote, SrcLocation::new(1, 3)),
+            Token::new(TokenKind::Id("a".into()), SrcLocation::new(1, 4)),
+            Token::new(TokenKind::SpliceUnquote, SrcLocation::new(1, 6)),
+            Token::new(TokenKind::Id("b".into()), SrcLocation::new(1, 8)),
+            Token::new(TokenKind::RParen, SrcLocation::new(1, 9)),
+        ];
+
+        let result = parse_all(&input).expect("Could not parse quasiquote");
+
+        assert_eq!(
+            vec![LanaExpr::List(
+                vec![
+                    LanaExpr::Symbol("quasiquote".into()),
+                    LanaExpr::List(
+                        vec![
+                            LanaExpr::List(
+                                vec![
+                                    LanaExpr::Symbol("unquote".into()),
+                                    LanaExpr::Symbol("a".into())
+                                ],
+                                None
+                            ),
+                            LanaExpr::List(
+                                vec![
+                                    LanaExpr::Symbol("splice-unquote".into()),
+                                    LanaExpr::Symbol("b".into())
+                                ],
+                                None
+                            ),
+                        ],
+                        None
+                    )
+                ],
+                None
+            )],
+            result
+        );
+    }
+
+    #[test]
+    fn it_parses_deref_shorthands() {
+        let input = vec![
+            Token::new(TokenKind::Deref, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::Id("counter".into()), SrcLocation::new(1, 8)),
+        ];
+
+        let result = parse_all(&input).expect("Could not parse deref");
+
+        assert_eq!(
+            vec![LanaExpr::List(
+                vec![
+                    LanaExpr::Symbol("lana.core/deref".into()),
+                    LanaExpr::Symbol("counter".into())
+                ],
+                None
+            )],
+            result
+        );
+    }
+
+    #[test]
+    fn it_errors_on_dangling_quotes() {
+        let quote = Token::new(TokenKind::Quote, SrcLocation::new(1, 1));
+
+        let input = vec![quote.clone()];
+
+        let result = parse_all(&input).expect_err("Didn't fail on dangling quote");
+
+        assert_eq!(LanaErr::UnexpectedToken(quote), result);
+    }
+
     #[test]
     #[should_panic(expected = "Cannot parse atom from token ')' at line 1, column 1")]
     fn it_panics_on_invalid_atoms() {
@@ -238,4 +671,32 @@ mod tests {
 
         parse_atom(&input).expect_err("Didn't panic on unexpected token");
     }
+
+    #[test]
+    fn it_records_where_lists_and_their_items_were_read_from() {
+        let input = vec![
+            Token::new(TokenKind::LParen, SrcLocation::new(1, 1)),
+            Token::new(TokenKind::Id("f".into()), SrcLocation::new(1, 2)),
+            Token::new(
+                TokenKind::Number(LanaNumber::Int(12)),
+                SrcLocation::new(1, 5),
+            )
+            .starting_at(SrcLocation::new(1, 4)),
+            Token::new(TokenKind::RParen, SrcLocation::new(2, 1)),
+        ];
+
+        let (result, _) = parse(&input).expect("Could not parse list");
+
+        let expected_spans = ListSpans {
+            span: SrcSpan::new(SrcLocation::new(1, 1), SrcLocation::new(2, 1)),
+            items: vec![
+                SrcSpan::new(SrcLocation::new(1, 2), SrcLocation::new(1, 2)),
+                SrcSpan::new(SrcLocation::new(1, 4), SrcLocation::new(1, 5)),
+            ],
+        };
+        match result {
+            LanaExpr::List(_, Some(spans)) => assert_eq!(expected_spans, *spans),
+            other => panic!("Expected a list with spans, got {:?}", other),
+        }
+    }
 }
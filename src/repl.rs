@@ -1,15 +1,12 @@
-use super::interpreter::eval;
-use super::parse;
-use super::LanaEnv;
-use super::LanaErr;
-use super::LanaExpr;
-use super::Tokenizer;
+use super::print_source_snippet;
+use super::print_stack_trace;
 use colored::Colorize;
+use lana::Interpreter;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
 pub fn repl() {
-    let env = &mut LanaEnv::default();
+    let lana = Interpreter::new();
     let mut rl = Editor::<()>::new();
 
     loop {
@@ -45,29 +42,23 @@ pub fn repl() {
             continue;
         }
 
-        match parse_eval(input, env) {
+        match lana.eval_str(&input) {
             Ok(res) => {
-                env.data.insert("_".into(), res.clone());
+                lana.define("_", res.clone());
 
                 println!("=> {}", res.to_colorized_string())
             }
             Err(e) => {
                 let s = format!("ERROR: {}.", e).bold().red().to_string();
 
-                println!("=> {}", s)
+                println!("=> {}", s);
+                print_source_snippet(&e, "<repl>", &input);
+                print_stack_trace(&e, "<repl>");
             }
         }
     }
 }
 
-fn parse_eval(expr: String, env: &mut LanaEnv) -> Result<LanaExpr, LanaErr> {
-    let tokens = Tokenizer::new(&expr).tokens();
-    let (parsed_expr, _) = parse(&tokens)?;
-    let evaled_expr = eval(&parsed_expr, env)?;
-
-    Ok(evaled_expr)
-}
-
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn prompt() -> String {
@@ -0,0 +1,204 @@
+//! Serde support for Lana values, so they can be read from and written to JSON, YAML, TOML, etc.
+//!
+//! Keywords and symbols are written as their names, lists, vectors and sets as sequences, and `nil`
+//! as the format's null. Sequences are read back as vectors. Functions can't be serialized.
+
+use super::{LanaError, LanaExpr, LanaNumber};
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::fmt;
+
+impl Serialize for LanaExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            LanaExpr::Nil => serializer.serialize_unit(),
+            LanaExpr::Bool(b) => serializer.serialize_bool(*b),
+            LanaExpr::Number(LanaNumber::Int(n)) => serializer.serialize_i64(*n),
+            // Big integers and ratios don't fit most formats, so they lose precision
+            LanaExpr::Number(n) => serializer.serialize_f64(n.to_f64()),
+            LanaExpr::String(s) | LanaExpr::Symbol(s) => serializer.serialize_str(s),
+            LanaExpr::Keyword(k) => serializer.serialize_str(&k[1..]),
+            LanaExpr::List(items, _) | LanaExpr::Vector(items) => {
+                serialize_seq(items.iter(), serializer)
+            }
+            LanaExpr::Set(set) => serialize_seq(set.iter(), serializer),
+            LanaExpr::Map(map) => {
+                let mut state = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    state.serialize_entry(key, value)?;
+                }
+
+                state.end()
+            }
+            LanaExpr::Record(record) => {
+                let mut state = serializer.serialize_map(Some(record.values.len()))?;
+                for (field, value) in record.fields() {
+                    state.serialize_entry(&field[1..], value)?;
+                }
+
+                state.end()
+            }
+            LanaExpr::Error(error) => serialize_error(error, serializer),
+            other => Err(ser::Error::custom(format!(
+                "Cannot serialize {}",
+                other.enum_name()
+            ))),
+        }
+    }
+}
+
+fn serialize_seq<'a, S: Serializer>(
+    items: impl ExactSizeIterator<Item = &'a LanaExpr>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_seq(Some(items.len()))?;
+    for item in items {
+        state.serialize_element(item)?;
+    }
+
+    state.end()
+}
+
+fn serialize_error<S: Serializer>(error: &LanaError, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_map(Some(3))?;
+    state.serialize_entry("kind", &error.kind[1..])?;
+    state.serialize_entry("message", &error.message)?;
+    state.serialize_entry("data", &error.data)?;
+
+    state.end()
+}
+
+impl<'de> Deserialize<'de> for LanaExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LanaExprVisitor)
+    }
+}
+
+struct LanaExprVisitor;
+
+impl<'de> Visitor<'de> for LanaExprVisitor {
+    type Value = LanaExpr;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a Lana value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::Nil)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<LanaExpr, D::Error> {
+        LanaExpr::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::int(n))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::Number(BigInt::from(n).into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::float(n))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::String(s.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<LanaExpr, E> {
+        Ok(LanaExpr::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<LanaExpr, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(LanaExpr::Vector(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<LanaExpr, A::Error> {
+        let mut entries = IndexMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+
+        Ok(LanaExpr::Map(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanaRecord, LanaRecordType};
+    use std::rc::Rc;
+
+    fn keyword(name: &str) -> LanaExpr {
+        LanaExpr::Keyword(name.into())
+    }
+
+    #[test]
+    fn it_serializes_values() {
+        let mut map = IndexMap::new();
+        map.insert(keyword(":name"), LanaExpr::String("lana".into()));
+        map.insert(
+            keyword(":tags"),
+            LanaExpr::List(vec![LanaExpr::int(1), LanaExpr::float(1.5)], None),
+        );
+        map.insert(keyword(":parent"), LanaExpr::Nil);
+
+        let json = serde_json::to_string(&LanaExpr::Map(map)).unwrap();
+
+        assert_eq!(r#"{"name":"lana","tags":[1,1.5],"parent":null}"#, json);
+    }
+
+    #[test]
+    fn it_serializes_records_as_maps() {
+        let record_type = Rc::new(LanaRecordType {
+            name: "Point".into(),
+            fields: vec![":x".into(), ":y".into()],
+        });
+        let point = LanaExpr::Record(Rc::new(LanaRecord {
+            record_type,
+            values: vec![LanaExpr::int(1), LanaExpr::int(2)],
+        }));
+
+        let json = serde_json::to_string(&point).unwrap();
+
+        assert_eq!(r#"{"x":1,"y":2}"#, json);
+    }
+
+    #[test]
+    fn it_deserializes_values() {
+        let value: LanaExpr = serde_json::from_str(r#"{"a": [1, true, null]}"#).unwrap();
+
+        let mut map = IndexMap::new();
+        map.insert(
+            LanaExpr::String("a".into()),
+            LanaExpr::Vector(vec![LanaExpr::int(1), LanaExpr::Bool(true), LanaExpr::Nil]),
+        );
+        assert_eq!(LanaExpr::Map(map), value);
+    }
+
+    #[test]
+    fn it_refuses_to_serialize_functions() {
+        let function = LanaExpr::Func(|_| Ok(LanaExpr::Nil));
+
+        let result = serde_json::to_string(&function);
+
+        assert_eq!("Cannot serialize function", result.unwrap_err().to_string());
+    }
+}